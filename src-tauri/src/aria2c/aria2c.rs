@@ -1,55 +1,150 @@
+use crate::aria2c::download_manager::{Aria2cClient, RpcEndpoint};
+use crate::aria2c::process_log::ProcessLog;
+use crate::config::settings::{DownloadSettings, ExternalDaemonSettings};
+use crate::error::AppError;
+use serde::Serialize;
+use std::net::TcpListener;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::Manager;
-use tauri_plugin_shell::process::CommandChild;
+use std::time::Duration;
+use tauri::async_runtime::Receiver;
+use tauri::{Emitter, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
 use tauri_plugin_shell::ShellExt;
+use tokio::sync::watch;
 
+/// 自动重启的等待时间上下限 (毫秒)
+const RESTART_MIN_DELAY_MS: u64 = 1_000;
+const RESTART_MAX_DELAY_MS: u64 = 60_000;
 
-// 添加新的引用
-use std::process::Command;
+/// 运行超过该时间 (毫秒) 后才崩溃，视为稳定运行过，重启退避从头开始
+const STABLE_RUN_RESET_MS: u64 = 60_000;
+
+/// aria2c 进程状态，通过 `aria2c-status` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Aria2cStatus {
+    Stopped,
+    Starting,
+    Running,
+    Crashed {
+        code: Option<i32>,
+        signal: Option<i32>,
+    },
+    Restarting {
+        attempt: u32,
+        delay_ms: u64,
+    },
+}
 
 #[derive(Clone)]
 pub struct Aria2cState {
     process: Arc<Mutex<Option<CommandChild>>>,
+    endpoint: Arc<Mutex<RpcEndpoint>>,
+    /// 连接参数变化时通知，通知监听任务据此断开旧连接
+    endpoint_changed: Arc<watch::Sender<()>>,
+    status: Arc<Mutex<Aria2cStatus>>,
+    /// 主动停止时置位，监视任务据此区分正常退出和崩溃
+    stopping: Arc<AtomicBool>,
 }
 
 impl Aria2cState {
-    pub fn new() -> Self {
+    pub fn new(endpoint: RpcEndpoint) -> Self {
         Self {
             process: Arc::new(Mutex::new(None)),
+            endpoint: Arc::new(Mutex::new(endpoint)),
+            endpoint_changed: Arc::new(watch::Sender::new(())),
+            status: Arc::new(Mutex::new(Aria2cStatus::Stopped)),
+            stopping: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    // 添加一个新的辅助函数来检查系统中是否已经运行了aria2c
-    fn is_aria2c_running() -> bool {
-        let output = if cfg!(target_os = "windows") {
-            Command::new("tasklist")
-                .args(&["/FI", "IMAGENAME eq aria2c.exe"])
-                .output()
-        } else {
-            Command::new("pgrep").arg("aria2c").output()
-        };
+    /// 当前进程状态
+    pub fn status(&self) -> Result<Aria2cStatus, AppError> {
+        Ok(self.status.lock()?.clone())
+    }
 
-        match output {
-            Ok(output) => {
-                if cfg!(target_os = "windows") {
-                    // 在Windows上检查输出是否包含aria2c进程信息
-                    String::from_utf8_lossy(&output.stdout).contains("aria2c.exe")
-                } else {
-                    // 在Unix系统上检查是否有进程ID返回
-                    !output.stdout.is_empty()
-                }
+    /// 更新进程状态并通知前端
+    fn set_status(&self, app_handle: &tauri::AppHandle, status: Aria2cStatus) {
+        if let Ok(mut guard) = self.status.lock() {
+            *guard = status.clone();
+        }
+        if let Err(e) = app_handle.emit("aria2c-status", &status) {
+            eprintln!("Failed to emit aria2c-status: {}", e);
+        }
+    }
+
+    /// 当前生效的 RPC 连接参数
+    pub fn endpoint(&self) -> Result<RpcEndpoint, AppError> {
+        Ok(self.endpoint.lock()?.clone())
+    }
+
+    /// 订阅连接参数的变化
+    pub fn watch_endpoint(&self) -> watch::Receiver<()> {
+        self.endpoint_changed.subscribe()
+    }
+
+    fn set_endpoint(&self, endpoint: RpcEndpoint) -> Result<(), AppError> {
+        *self.endpoint.lock()? = endpoint;
+        self.endpoint_changed.send_replace(());
+        Ok(())
+    }
+
+    /// 使用当前连接参数创建 RPC 客户端
+    pub fn client(&self) -> Result<Aria2cClient, AppError> {
+        Ok(Aria2cClient::new(&self.endpoint()?))
+    }
+
+    /// 首选端口被占用时，由系统分配一个空闲端口
+    ///
+    /// aria2c 以 `--rpc-listen-all` 监听所有网卡，因此同样在 0.0.0.0 上检查端口
+    fn pick_rpc_port(preferred: u16) -> u16 {
+        if TcpListener::bind(("0.0.0.0", preferred)).is_ok() {
+            return preferred;
+        }
+
+        match TcpListener::bind(("0.0.0.0", 0)).and_then(|listener| listener.local_addr()) {
+            Ok(addr) => {
+                println!(
+                    "RPC port {} is in use, falling back to {}",
+                    preferred,
+                    addr.port()
+                );
+                addr.port()
+            }
+            Err(e) => {
+                eprintln!("Failed to find a free RPC port: {}", e);
+                preferred
             }
-            Err(_) => false,
         }
     }
 
-    pub async fn start_aria2c(&self, app_handle: tauri::AppHandle) -> Result<(), String> {
+    /// 切换到外部守护进程：先验证连接，成功后再停止本地 aria2c
+    pub async fn use_external_daemon(
+        &self,
+        endpoint: RpcEndpoint,
+        shutdown_timeout: Duration,
+    ) -> Result<serde_json::Value, AppError> {
+        let version = Aria2cClient::new(&endpoint).get_version().await?;
+        self.stop_aria2c(shutdown_timeout).await?;
+        self.set_endpoint(endpoint)?;
+        Ok(version)
+    }
+
+    /// 切回本地 aria2c
+    pub async fn use_local_daemon(
+        &self,
+        endpoint: RpcEndpoint,
+        app_handle: tauri::AppHandle,
+    ) -> Result<(), AppError> {
+        self.set_endpoint(endpoint)?;
+        self.start_aria2c(app_handle).await
+    }
+
+    pub async fn start_aria2c(&self, app_handle: tauri::AppHandle) -> Result<(), AppError> {
         {
-            let process_guard = self
-                .process
-                .lock()
-                .map_err(|e| format!("Failed to acquire lock: {}", e))?;
+            let process_guard = self.process.lock()?;
 
             // 如果进程已经在运行，则不再启动
             if process_guard.is_some() {
@@ -57,13 +152,41 @@ impl Aria2cState {
             }
         } // MutexGuard 在这里被释放
 
-        // 检查系统中是否已经运行了aria2c进程
-        if Self::is_aria2c_running() {
-            println!("aria2c is already running on the system, skipping start");
+        // 外部守护进程不由本程序启动，只验证地址和密钥
+        let endpoint = self.endpoint()?;
+        if endpoint.external {
+            let version = Aria2cClient::new(&endpoint).get_version().await?;
+            println!(
+                "Using external aria2 daemon at {}: {}",
+                endpoint.http_url(),
+                version
+            );
+            self.set_status(&app_handle, Aria2cStatus::Running);
             return Ok(());
         }
 
+        self.stopping.store(false, Ordering::SeqCst);
+        self.set_status(&app_handle, Aria2cStatus::Starting);
+
+        let rx = match self.spawn_sidecar(&app_handle) {
+            Ok(rx) => rx,
+            Err(e) => {
+                self.set_status(&app_handle, Aria2cStatus::Stopped);
+                return Err(e);
+            }
+        };
+        self.set_status(&app_handle, Aria2cStatus::Running);
+        self.supervise(app_handle, rx);
 
+        println!("aria2c started successfully with RPC enabled and session persistence");
+        Ok(())
+    }
+
+    /// 启动 aria2c sidecar 并保存进程句柄，返回进程事件接收端
+    fn spawn_sidecar(
+        &self,
+        app_handle: &tauri::AppHandle,
+    ) -> Result<Receiver<CommandEvent>, AppError> {
         let mut session_path = app_handle.path().app_data_dir().unwrap();
 
         session_path =  session_path.join("aria2c_session.txt");
@@ -72,14 +195,22 @@ impl Aria2cState {
 
         if !Path::new(&session_path.display().to_string()).exists() {
             // 如果没有会话文件，则创建一个空的会话文件
-            std::fs::File::create(&session_path.display().to_string())
-                .map_err(|e| format!("Failed to create session file: {}", e))?;
+            std::fs::File::create(&session_path.display().to_string())?;
         }
 
-        let shell = app_handle.shell();
+        // 选定实际使用的端口，并写回状态供所有客户端读取
+        let mut endpoint = self.endpoint()?;
+        let port = Self::pick_rpc_port(endpoint.port);
+        if port != endpoint.port {
+            endpoint.port = port;
+            self.set_endpoint(endpoint.clone())?;
+        }
 
+        let shell = app_handle.shell();
         // 启动aria2c进程，添加持久化参数
-        let (_rx, child) = shell.sidecar("aria2c").unwrap()
+        let (rx, child) = shell
+            .sidecar("aria2c")
+            .map_err(|e| AppError::Process(format!("Failed to resolve aria2c sidecar: {}", e)))?
             .args([
                 "--enable-rpc",
                 "--rpc-listen-all", 
@@ -87,7 +218,8 @@ impl Aria2cState {
                 "--seed-ratio=1.0",
                 "--seed-time=1",
                 "--bt-tracker=\"udp://tracker.opentrackr.org:1337/announce,http://tracker.dler.org:6969/announce,udp://open.tracker.cl:1337/announce,udp://tracker.openbittorrent.com:80/announce\"",
-                "--rpc-secret=game_app_secret_2024",
+                format!("--rpc-listen-port={}", endpoint.port).as_str(),
+                format!("--rpc-secret={}", endpoint.secret).as_str(),
                 format!("--save-session={}", session_path.display().to_string()).as_str(),
                 format!("--input-file={}", session_path.display().to_string()).as_str(),
                 "--continue=true",
@@ -96,57 +228,163 @@ impl Aria2cState {
             ])
    
             .spawn()
-            .map_err(|e| {
-                format!("Failed to start aria2c: {}", e)
-            })?;
+            .map_err(|e| AppError::Process(format!("Failed to start aria2c: {}", e)))?;
 
         // 将进程保存到状态中
         {
-            let mut process_guard = self
-                .process
-                .lock()
-                .map_err(|e| format!("Failed to acquire lock: {}", e))?;
+            let mut process_guard = self.process.lock()?;
             *process_guard = Some(child);
         }
 
-        println!("aria2c started successfully with RPC enabled and session persistence");
-        Ok(())
+        Ok(rx)
     }
 
-    pub async fn stop_aria2c(&self) -> Result<(), String> {
-        let child_option = {
-            let mut process_guard = self
-                .process
-                .lock()
-                .map_err(|e| format!("Failed to acquire lock: {}", e))?;
-            process_guard.take()
-        };
+    /// 监视 aria2c 进程：输出写入滚动日志，意外退出时按指数退避自动重启
+    fn supervise(&self, app_handle: tauri::AppHandle, mut rx: Receiver<CommandEvent>) {
+        let state = self.clone();
+        let log_path = app_handle
+            .path()
+            .app_log_dir()
+            .unwrap_or_else(|_| std::env::temp_dir())
+            .join("aria2c.log");
+
+        tauri::async_runtime::spawn(async move {
+            let mut log = ProcessLog::open(log_path);
+            let mut attempt: u32 = 0;
+
+            loop {
+                let started = tokio::time::Instant::now();
+                let mut terminated = None;
+                while let Some(event) = rx.recv().await {
+                    match event {
+                        CommandEvent::Stdout(line) => log.write_line("stdout", &line),
+                        CommandEvent::Stderr(line) => log.write_line("stderr", &line),
+                        CommandEvent::Error(e) => log.write_line("error", e.as_bytes()),
+                        CommandEvent::Terminated(payload) => {
+                            terminated = Some(payload);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+
+                // 进程已经退出，清理保存的句柄
+                if let Ok(mut process_guard) = state.process.lock() {
+                    process_guard.take();
+                }
 
-        if let Some(child) = child_option {
-            // 发送关闭信号给aria2c，让它保存会话
-            let client = reqwest::Client::new();
-            let shutdown_request = serde_json::json!({
-                "jsonrpc": "2.0",
-                "id": "shutdown",
-                "method": "aria2.forceShutdown",
-                "params": ["token:game_app_secret_2024"]
-            });
-
-            // 尝试发送关闭请求
-            let _ = client
-                .post("http://127.0.0.1:6800/jsonrpc")
-                .json(&shutdown_request)
-                .send()
-                .await;
-
-            // 等待一段时间让aria2c保存会话
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
-            // 杀死进程
+                if state.stopping.load(Ordering::SeqCst) {
+                    log.write_line("supervisor", b"aria2c stopped");
+                    state.set_status(&app_handle, Aria2cStatus::Stopped);
+                    return;
+                }
+
+                let (code, signal) = terminated
+                    .map(|payload| (payload.code, payload.signal))
+                    .unwrap_or((None, None));
+                log.write_line(
+                    "supervisor",
+                    format!(
+                        "aria2c exited unexpectedly (code: {:?}, signal: {:?})",
+                        code, signal
+                    )
+                    .as_bytes(),
+                );
+                state.set_status(&app_handle, Aria2cStatus::Crashed { code, signal });
+
+                // 稳定运行过一段时间后才崩溃，重新从最短间隔开始退避
+                let stable_for = tokio::time::Duration::from_millis(STABLE_RUN_RESET_MS);
+                if started.elapsed() >= stable_for {
+                    attempt = 0;
+                }
+
+                loop {
+                    attempt += 1;
+                    let delay_ms = RESTART_MIN_DELAY_MS
+                        .saturating_mul(1 << (attempt - 1).min(16))
+                        .min(RESTART_MAX_DELAY_MS);
+                    state.set_status(&app_handle, Aria2cStatus::Restarting { attempt, delay_ms });
+                    tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+
+                    // 等待期间被主动停止，或者已经被手动重新启动
+                    let already_running = state
+                        .process
+                        .lock()
+                        .map(|guard| guard.is_some())
+                        .unwrap_or(true);
+                    if state.stopping.load(Ordering::SeqCst) || already_running {
+                        return;
+                    }
+
+                    state.set_status(&app_handle, Aria2cStatus::Starting);
+                    match state.spawn_sidecar(&app_handle) {
+                        Ok(new_rx) => {
+                            rx = new_rx;
+                            log.write_line(
+                                "supervisor",
+                                format!("aria2c restarted (attempt {})", attempt).as_bytes(),
+                            );
+                            state.set_status(&app_handle, Aria2cStatus::Running);
+                            break;
+                        }
+                        Err(e) => {
+                            log.write_line(
+                                "supervisor",
+                                format!("Failed to restart aria2c: {}", e).as_bytes(),
+                            );
+                            state.set_status(
+                                &app_handle,
+                                Aria2cStatus::Crashed {
+                                    code: None,
+                                    signal: None,
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// 有序关闭 aria2c：保存会话 → aria2.shutdown → 等待进程退出，超时后强制结束
+    pub async fn stop_aria2c(&self, timeout: Duration) -> Result<(), AppError> {
+        // 先标记为主动停止，避免监视任务把退出当作崩溃重新拉起
+        self.stopping.store(true, Ordering::SeqCst);
+
+        if self.process.lock()?.is_none() {
+            return Ok(());
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let client = self.client()?;
+
+        // 等待会话保存完成后再请求退出，避免丢失任务
+        match tokio::time::timeout_at(deadline, client.save_session()).await {
+            Ok(Ok(())) => println!("aria2c session saved"),
+            Ok(Err(e)) => eprintln!("Failed to save aria2c session: {}", e),
+            Err(_) => eprintln!("Timed out saving aria2c session"),
+        }
+        match tokio::time::timeout_at(deadline, client.shutdown()).await {
+            Ok(Ok(())) => println!("aria2c shutdown requested"),
+            Ok(Err(e)) => eprintln!("Failed to request aria2c shutdown: {}", e),
+            Err(_) => eprintln!("Timed out requesting aria2c shutdown"),
+        }
+
+        // 进程退出后监视任务会清空句柄
+        while tokio::time::Instant::now() < deadline {
+            if self.process.lock()?.is_none() {
+                println!("aria2c process stopped");
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        // 超时仍未退出，强制结束进程
+        if let Some(child) = self.process.lock()?.take() {
+            eprintln!("aria2c did not exit within {:?}, killing it", timeout);
             if let Err(e) = child.kill() {
                 println!("Warning: Failed to kill aria2c process: {}", e);
             }
-            println!("aria2c process stopped");
         }
         Ok(())
     }
@@ -157,22 +395,80 @@ impl Aria2cState {
 pub async fn start_aria2c(
     state: tauri::State<'_, Aria2cState>,
     app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     state.start_aria2c(app).await
 }
 
 #[tauri::command]
-pub async fn stop_aria2c(state: tauri::State<'_, Aria2cState>) -> Result<(), String> {
-    state.stop_aria2c().await
+pub async fn stop_aria2c(
+    state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<(), AppError> {
+    let timeout = settings_state.lock()?.shutdown_timeout();
+    state.stop_aria2c(timeout).await
 }
 
 #[tauri::command]
-pub async fn get_aria2c_info() -> Result<serde_json::Value, String> {
+pub async fn get_aria2c_info(
+    state: tauri::State<'_, Aria2cState>,
+) -> Result<serde_json::Value, AppError> {
+    let endpoint = state.endpoint()?;
     let info = serde_json::json!({
-        "rpc_url": "http://127.0.0.1:6800/jsonrpc",
-        "rpc_secret": "game_app_secret_2024",
-        "status": "Ready for connections",
+        "rpc_url": endpoint.http_url(),
+        "rpc_secret": endpoint.secret,
+        "mode": if endpoint.external { "external" } else { "local" },
+        "status": state.status()?,
         "persistence": "Enabled with session file"
     });
     Ok(info)
 }
+
+/// 根据设置生成启动时使用的连接参数，外部守护进程地址无效时回退到本地 aria2c
+pub fn endpoint_from_settings(settings: &DownloadSettings) -> RpcEndpoint {
+    if let Some(external) = &settings.external_daemon {
+        match RpcEndpoint::external(&external.rpc_url, external.rpc_secret.clone()) {
+            Ok(endpoint) => return endpoint,
+            Err(e) => eprintln!("Ignoring invalid external daemon settings: {}", e),
+        }
+    }
+    RpcEndpoint::new(settings.rpc_port, settings.rpc_secret.clone())
+}
+
+// Tauri命令：连接外部 aria2 守护进程，通过 aria2.getVersion 验证后保存到设置
+#[tauri::command]
+pub async fn connect_external_daemon(
+    rpc_url: String,
+    rpc_secret: String,
+    state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<serde_json::Value, AppError> {
+    let endpoint = RpcEndpoint::external(&rpc_url, rpc_secret.clone())?;
+    let shutdown_timeout = settings_state.lock()?.shutdown_timeout();
+    let version = state.use_external_daemon(endpoint, shutdown_timeout).await?;
+
+    let mut settings = settings_state.lock()?;
+    settings.external_daemon = Some(ExternalDaemonSettings {
+        rpc_url,
+        rpc_secret,
+    });
+    settings.save()?;
+
+    Ok(version)
+}
+
+// Tauri命令：断开外部守护进程，改用本地 aria2c
+#[tauri::command]
+pub async fn disconnect_external_daemon(
+    state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    app: tauri::AppHandle,
+) -> Result<(), AppError> {
+    let endpoint = {
+        let mut settings = settings_state.lock()?;
+        settings.external_daemon = None;
+        settings.save()?;
+        endpoint_from_settings(&settings)
+    };
+
+    state.use_local_daemon(endpoint, app).await
+}
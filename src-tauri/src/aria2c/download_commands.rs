@@ -1,24 +1,36 @@
-use crate::aria2c::download_manager::{Aria2cClient, DownloadFile, DownloadTask, PeerInfo};
-use crate::config::settings::{DownloadSettings, NewTaskSettings};
+use crate::aria2c::aria2c::Aria2cState;
+use crate::aria2c::download_manager::{
+    Aria2cClient, DownloadFile, DownloadTask, PeerInfo, QueueMove, TaskFilter, TaskPage, TaskSource,
+};
+use crate::aria2c::file_selection::FileSelection;
+use crate::aria2c::restart::restart_task;
+use crate::config::category::TaskDescriptor;
+use crate::config::settings::{DownloadSettings, NewTaskSettings, TaskSettings};
+use crate::error::AppError;
+use crate::torrent::cache::TorrentCache;
+use crate::torrent::magnet::MagnetLink;
+use crate::torrent::meta::TorrentMeta;
 use base64::Engine;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use tauri::Emitter;
 
 // Tauri命令：通过URL添加下载任务（支持设置）
 #[tauri::command]
 pub async fn add_download_url(
     urls: Vec<String>,
+    aria2c_state: tauri::State<'_, Aria2cState>,
     settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
     task_settings: Option<NewTaskSettings>,
-) -> Result<String, String> {
-    let client = Aria2cClient::new();
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
 
-    // 从全局设置和任务设置生成 aria2c 选项
+    // 从全局设置、分类和任务设置生成 aria2c 选项
     let options = {
-        let global_settings = settings_state
-            .lock()
-            .map_err(|e| format!("无法获取设置锁: {}", e))?;
-        global_settings.to_aria2c_options(task_settings.as_ref())
+        let global_settings = settings_state.lock()?;
+        global_settings
+            .to_aria2c_options(task_settings.as_ref(), &TaskDescriptor::from_uris(&urls))?
     };
 
     client.add_uri(urls, Some(options)).await
@@ -28,24 +40,43 @@ pub async fn add_download_url(
 #[tauri::command]
 pub async fn add_download_torrent(
     torrent_path: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
     settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    torrent_cache: tauri::State<'_, TorrentCache>,
     task_settings: Option<NewTaskSettings>,
-) -> Result<String, String> {
-    let client = Aria2cClient::new();
+    file_selection: Option<FileSelection>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
 
     // 读取种子文件
-    let torrent_data = tokio::fs::read(&torrent_path)
-        .await
-        .map_err(|e| format!("Failed to read torrent file: {}", e))?;
+    let torrent_data = tokio::fs::read(&torrent_path).await?;
+
+    // 校验种子并保存副本，重启任务时使用
+    let meta = torrent_cache.store(&torrent_data)?;
 
-    // 从全局设置和任务设置生成 aria2c 选项
+    // 从全局设置、分类和任务设置生成 aria2c 选项
     let options = {
-        let global_settings = settings_state
-            .lock()
-            .map_err(|e| format!("无法获取设置锁: {}", e))?;
-        global_settings.to_aria2c_options(task_settings.as_ref())
+        let global_settings = settings_state.lock()?;
+        global_settings
+            .to_aria2c_options(task_settings.as_ref(), &TaskDescriptor::from_torrent(&meta))?
     };
 
+    add_torrent_with_selection(&client, torrent_data, &meta, options, file_selection).await
+}
+
+/// 添加种子任务，并按选择条件只下载部分文件
+///
+/// 种子已经解析，直接按种子中的文件列表生成 `select-file`，随任务一起传给 aria2
+async fn add_torrent_with_selection(
+    client: &Aria2cClient,
+    torrent_data: Vec<u8>,
+    meta: &TorrentMeta,
+    mut options: HashMap<String, String>,
+    file_selection: Option<FileSelection>,
+) -> Result<String, AppError> {
+    if let Some(selection) = file_selection {
+        options.insert("select-file".to_string(), selection.resolve_torrent(meta)?);
+    }
     client.add_torrent(torrent_data, None, Some(options)).await
 }
 
@@ -53,43 +84,94 @@ pub async fn add_download_torrent(
 #[tauri::command]
 pub async fn add_download_torrent_base64(
     torrent_base64: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
     settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    torrent_cache: tauri::State<'_, TorrentCache>,
     task_settings: Option<NewTaskSettings>,
-) -> Result<String, String> {
-    let client = Aria2cClient::new();
+    file_selection: Option<FileSelection>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
 
     println!("add base:{}", torrent_base64);
     // 解码Base64格式的种子内容
-    let torrent_data = base64::engine::general_purpose::STANDARD
-        .decode(&torrent_base64)
-        .map_err(|e| format!("Failed to decode base64 torrent data: {}", e))?;
+    let torrent_data = base64::engine::general_purpose::STANDARD.decode(&torrent_base64)?;
 
-    // 从全局设置和任务设置生成 aria2c 选项
+    // 校验种子并保存副本，重启任务时使用
+    let meta = torrent_cache.store(&torrent_data)?;
+
+    // 从全局设置、分类和任务设置生成 aria2c 选项
     let options = {
-        let global_settings = settings_state
-            .lock()
-            .map_err(|e| format!("无法获取设置锁: {}", e))?;
-        global_settings.to_aria2c_options(task_settings.as_ref())
+        let global_settings = settings_state.lock()?;
+        global_settings
+            .to_aria2c_options(task_settings.as_ref(), &TaskDescriptor::from_torrent(&meta))?
     };
 
-    client.add_torrent(torrent_data, None, Some(options)).await
+    add_torrent_with_selection(&client, torrent_data, &meta, options, file_selection).await
+}
+
+// Tauri命令：通过 Metalink 文件 (.meta4/.metalink) 添加下载任务（支持设置），返回创建的全部 GID
+#[tauri::command]
+pub async fn add_download_metalink(
+    metalink_path: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    task_settings: Option<NewTaskSettings>,
+) -> Result<Vec<String>, AppError> {
+    let client = aria2c_state.client()?;
+
+    // 读取 Metalink 文件
+    let metalink_data = tokio::fs::read(&metalink_path).await?;
+
+    // 从全局设置、分类和任务设置生成 aria2c 选项
+    let options = {
+        let global_settings = settings_state.lock()?;
+        global_settings.to_aria2c_options(task_settings.as_ref(), &TaskDescriptor::metalink())?
+    };
+
+    client.add_metalink(metalink_data, Some(options)).await
+}
+
+// Tauri命令：通过Base64编码的 Metalink 内容添加下载任务（支持设置），返回创建的全部 GID
+#[tauri::command]
+pub async fn add_download_metalink_base64(
+    metalink_base64: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    task_settings: Option<NewTaskSettings>,
+) -> Result<Vec<String>, AppError> {
+    let client = aria2c_state.client()?;
+
+    // 解码Base64格式的 Metalink 内容
+    let metalink_data = base64::engine::general_purpose::STANDARD.decode(&metalink_base64)?;
+
+    // 从全局设置、分类和任务设置生成 aria2c 选项
+    let options = {
+        let global_settings = settings_state.lock()?;
+        global_settings.to_aria2c_options(task_settings.as_ref(), &TaskDescriptor::metalink())?
+    };
+
+    client.add_metalink(metalink_data, Some(options)).await
 }
 
 // Tauri命令：通过磁力链接添加下载任务（支持设置）
 #[tauri::command]
 pub async fn add_download_magnet(
     magnet_link: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
     settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
     task_settings: Option<NewTaskSettings>,
-) -> Result<String, String> {
-    let client = Aria2cClient::new();
+) -> Result<String, AppError> {
+    // 先在本地校验，给出比 aria2 更明确的错误
+    let magnet = MagnetLink::parse(&magnet_link)?;
+    let client = aria2c_state.client()?;
 
-    // 从全局设置和任务设置生成 aria2c 选项
+    // 从全局设置、分类和任务设置生成 aria2c 选项
     let options = {
-        let global_settings = settings_state
-            .lock()
-            .map_err(|e| format!("无法获取设置锁: {}", e))?;
-        global_settings.to_aria2c_options(task_settings.as_ref())
+        let global_settings = settings_state.lock()?;
+        global_settings.to_aria2c_options(
+            task_settings.as_ref(),
+            &TaskDescriptor::from_magnet(&magnet),
+        )?
     };
 
     client.add_uri(vec![magnet_link], Some(options)).await
@@ -97,8 +179,11 @@ pub async fn add_download_magnet(
 
 // Tauri命令：获取指定下载任务的状态
 #[tauri::command]
-pub async fn get_download_status(gid: String) -> Result<DownloadTask, String> {
-    let client = Aria2cClient::new();
+pub async fn get_download_status(
+    gid: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<DownloadTask, AppError> {
+    let client = aria2c_state.client()?;
     client.get_download_status(&gid).await
 }
 
@@ -108,105 +193,377 @@ pub async fn get_download_status(gid: String) -> Result<DownloadTask, String> {
 pub async fn tell_status(
     gid: String,
     keys: Option<Vec<String>>,
-) -> Result<serde_json::Value, String> {
-    let client = Aria2cClient::new();
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<serde_json::Value, AppError> {
+    let client = aria2c_state.client()?;
     client.tell_status(&gid, keys).await
 }
 
-// Tauri命令：获取所有活动下载任务
+/// 不指定 limit 时每页返回的任务数
+const DEFAULT_PAGE_SIZE: u64 = 100;
+
+// Tauri命令：获取所有活动下载任务（支持分页和过滤）
+#[tauri::command]
+pub async fn get_active_downloads(
+    offset: Option<u64>,
+    limit: Option<u64>,
+    filter: Option<TaskFilter>,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<Vec<DownloadTask>, AppError> {
+    let client = aria2c_state.client()?;
+    let page = client
+        .list_tasks(
+            TaskSource::Active,
+            offset.unwrap_or(0),
+            limit.unwrap_or(u64::MAX),
+            filter.as_ref(),
+        )
+        .await?;
+    Ok(page.tasks)
+}
+
+// Tauri命令：获取等待中的下载任务（支持分页和过滤）
 #[tauri::command]
-pub async fn get_active_downloads() -> Result<Vec<DownloadTask>, String> {
-    let client = Aria2cClient::new();
-    client.get_active_downloads().await
+pub async fn get_waiting_downloads(
+    offset: Option<u64>,
+    limit: Option<u64>,
+    filter: Option<TaskFilter>,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<Vec<DownloadTask>, AppError> {
+    let client = aria2c_state.client()?;
+    let page = client
+        .list_tasks(
+            TaskSource::Waiting,
+            offset.unwrap_or(0),
+            limit.unwrap_or(DEFAULT_PAGE_SIZE),
+            filter.as_ref(),
+        )
+        .await?;
+    Ok(page.tasks)
 }
 
-// Tauri命令：获取等待中的下载任务
+// Tauri命令：获取已停止的下载任务（支持分页和过滤）
 #[tauri::command]
-pub async fn get_waiting_downloads() -> Result<Vec<DownloadTask>, String> {
-    let client = Aria2cClient::new();
-    client.get_waiting_downloads().await
+pub async fn get_stopped_downloads(
+    offset: Option<u64>,
+    limit: Option<u64>,
+    filter: Option<TaskFilter>,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<Vec<DownloadTask>, AppError> {
+    let client = aria2c_state.client()?;
+    let page = client
+        .list_tasks(
+            TaskSource::Stopped,
+            offset.unwrap_or(0),
+            limit.unwrap_or(DEFAULT_PAGE_SIZE),
+            filter.as_ref(),
+        )
+        .await?;
+    Ok(page.tasks)
 }
 
-// Tauri命令：获取已停止的下载任务
+// Tauri命令：合并活动、等待和已停止的任务，返回当前页和过滤后的总数
 #[tauri::command]
-pub async fn get_stopped_downloads() -> Result<Vec<DownloadTask>, String> {
-    let client = Aria2cClient::new();
-    client.get_stopped_downloads().await
+pub async fn list_downloads(
+    offset: Option<u64>,
+    limit: Option<u64>,
+    filter: Option<TaskFilter>,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<TaskPage, AppError> {
+    let client = aria2c_state.client()?;
+    client
+        .list_all_tasks(
+            offset.unwrap_or(0),
+            limit.unwrap_or(DEFAULT_PAGE_SIZE),
+            filter.as_ref(),
+        )
+        .await
 }
 
 // Tauri命令：暂停下载任务
 #[tauri::command]
-pub async fn pause_download(gid: String) -> Result<String, String> {
-    let client = Aria2cClient::new();
+pub async fn pause_download(
+    gid: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
     client.pause_download(&gid).await
 }
 
 // Tauri命令：恢复下载任务
 #[tauri::command]
-pub async fn resume_download(gid: String) -> Result<String, String> {
-    let client = Aria2cClient::new();
+pub async fn resume_download(
+    gid: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
     client.unpause_download(&gid).await
 }
 
 // Tauri命令：重启下载任务
+//
+// 种子任务使用缓存的种子文件（没有缓存时使用 info-hash 生成的磁力链接），
+// 普通任务和 Metalink 任务使用原来的全部镜像地址；新任务沿用原任务的全部选项
 #[tauri::command]
-pub async fn restart_download(gid: String) -> Result<String, String> {
-    let client = Aria2cClient::new();
+pub async fn restart_download(
+    gid: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    torrent_cache: tauri::State<'_, TorrentCache>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
+    let new_gid = restart_task(&client, &torrent_cache, &gid).await?;
+
+    // 保存的任务选项跟随到新的 GID
+    let mut settings = settings_state.lock()?;
+    if let Some(task_settings) = settings.task_settings.remove(&gid) {
+        settings
+            .task_settings
+            .insert(new_gid.clone(), task_settings);
+        settings.save()?;
+    }
 
-    // 先获取任务信息
-    let task_info = client.get_download_status(&gid).await?;
+    Ok(new_gid)
+}
 
-    // 删除原任务
-    let _ = client.remove_download(&gid).await;
+// Tauri命令：删除下载任务
+#[tauri::command]
+pub async fn remove_download(
+    gid: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
+    let result = client.remove_download(&gid).await?;
+
+    // 任务已删除，不再需要保存它的选项
+    let mut settings = settings_state.lock()?;
+    if settings.task_settings.remove(&gid).is_some() {
+        settings.save()?;
+    }
 
-    // 根据任务类型重新添加任务
-    if let Some(ref files) = task_info.files.first() {
-        if !files.uris.is_empty() {
-            // URL下载任务
-            let uris: Vec<String> = files.uris.iter().map(|uri| uri.uri.clone()).collect();
-            let mut options = std::collections::HashMap::new();
-            if let Some(ref dir) = task_info.dir {
-                options.insert("dir".to_string(), dir.clone());
-            }
-            return client.add_uri(uris, Some(options)).await;
+    Ok(result)
+}
+
+// Tauri命令：获取任务当前生效的 aria2 选项
+#[tauri::command]
+pub async fn get_task_options(
+    gid: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<HashMap<String, String>, AppError> {
+    let client = aria2c_state.client()?;
+    client.get_option(&gid).await
+}
+
+// Tauri命令：修改任务的速度限制、连接数、下载目录和做种规则，并保存以便 aria2c 重启后重新应用
+#[tauri::command]
+pub async fn change_task_options(
+    gid: String,
+    task_settings: TaskSettings,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<TaskSettings, AppError> {
+    let client = aria2c_state.client()?;
+
+    // 下载目录只能在任务开始下载前修改
+    if task_settings.download_dir.is_some() {
+        let status = client
+            .tell_status(
+                &gid,
+                Some(vec!["status".to_string(), "completedLength".to_string()]),
+            )
+            .await?;
+        let waiting = matches!(
+            status.get("status").and_then(|v| v.as_str()),
+            Some("waiting") | Some("paused")
+        );
+        let started = status
+            .get("completedLength")
+            .and_then(|v| v.as_str())
+            .is_some_and(|length| length != "0");
+        if !waiting || started {
+            return Err(AppError::InvalidInput(
+                "The download directory can only be changed before the task starts".to_string(),
+            ));
         }
     }
 
-    // 如果无法重启，返回错误
-    Err("无法重启任务，缺少必要的任务信息".to_string())
+    let options = task_settings.to_aria2c_options(true);
+    if options.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No task options to change".to_string(),
+        ));
+    }
+    client.change_option(&gid, options).await?;
+
+    let mut settings = settings_state.lock()?;
+    let merged = {
+        let entry = settings.task_settings.entry(gid).or_default();
+        entry.merge(&task_settings);
+        entry.clone()
+    };
+    settings.save()?;
+
+    Ok(merged)
 }
 
-// Tauri命令：删除下载任务
-#[tauri::command]
-pub async fn remove_download(gid: String) -> Result<String, String> {
-    let client = Aria2cClient::new();
-    client.remove_download(&gid).await
+/// 重新应用保存的任务选项，aria2c 启动或重启后调用；已不存在的任务会被清理
+pub async fn reapply_task_settings(
+    aria2c_state: &Aria2cState,
+    settings_state: &Arc<Mutex<DownloadSettings>>,
+) -> Result<(), AppError> {
+    let task_settings = settings_state.lock()?.task_settings.clone();
+    if task_settings.is_empty() {
+        return Ok(());
+    }
+
+    let client = aria2c_state.client()?;
+    let mut stale = Vec::new();
+    for (gid, settings) in &task_settings {
+        // 目录已写入 aria2 的会话，重新设置会让活动任务重新开始，这里跳过
+        let options = settings.to_aria2c_options(false);
+        if options.is_empty() {
+            continue;
+        }
+        match client.change_option(gid, options).await {
+            Ok(()) => {}
+            // 已停止的任务同样无法修改选项，用 tellStatus 确认任务不会再运行后才删除
+            Err(AppError::Rpc { message, .. }) => {
+                let keys = Some(vec!["status".to_string()]);
+                let finished = match client.tell_status(gid, keys).await {
+                    Ok(status) => matches!(
+                        status.get("status").and_then(|v| v.as_str()),
+                        Some("complete" | "error" | "removed")
+                    ),
+                    Err(AppError::Rpc { message, .. }) => message.contains("not found"),
+                    Err(e) => return Err(e),
+                };
+                if finished {
+                    println!("Dropping task settings for {}: {}", gid, message);
+                    stale.push(gid.clone());
+                } else {
+                    eprintln!("Failed to reapply task settings for {}: {}", gid, message);
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if !stale.is_empty() {
+        let mut settings = settings_state.lock()?;
+        for gid in &stale {
+            settings.task_settings.remove(gid);
+        }
+        settings.save()?;
+    }
+
+    Ok(())
 }
 
 // Tauri命令：清理已完成/错误/已删除的下载任务
 #[tauri::command]
-pub async fn purge_download_result() -> Result<String, String> {
-    let client = Aria2cClient::new();
+pub async fn purge_download_result(
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
     client.purge_download_result().await
 }
 
+/// 等待队列的新顺序，通过 `queue-changed` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueOrder {
+    pub gids: Vec<String>,
+}
+
+/// 读取等待队列的最新顺序并通知前端
+async fn emit_queue_order(
+    app: &tauri::AppHandle,
+    client: &Aria2cClient,
+) -> Result<QueueOrder, AppError> {
+    let order = QueueOrder {
+        gids: client.get_waiting_order().await?,
+    };
+    if let Err(e) = app.emit("queue-changed", &order) {
+        eprintln!("Failed to emit queue-changed: {}", e);
+    }
+    Ok(order)
+}
+
+// Tauri命令：在等待队列中移动任务（队首、队尾、指定位置或相对移动），返回新的位置
+#[tauri::command]
+pub async fn move_download(
+    gid: String,
+    to: QueueMove,
+    app: tauri::AppHandle,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<u64, AppError> {
+    let client = aria2c_state.client()?;
+    let position = client.change_position(&gid, to).await?;
+    emit_queue_order(&app, &client).await?;
+    Ok(position)
+}
+
+// Tauri命令：把多个任务按给定顺序移到队首，返回新的队列顺序
+#[tauri::command]
+pub async fn move_downloads_to_front(
+    gids: Vec<String>,
+    app: tauri::AppHandle,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<QueueOrder, AppError> {
+    let client = aria2c_state.client()?;
+
+    // 倒序逐个移到队首，最终保持传入的先后顺序
+    for gid in gids.iter().rev() {
+        client.change_position(gid, QueueMove::Top).await?;
+    }
+
+    emit_queue_order(&app, &client).await
+}
+
+// Tauri命令：修改运行中 BT 任务要下载的文件，返回更新后的文件列表
+#[tauri::command]
+pub async fn select_download_files(
+    gid: String,
+    file_selection: FileSelection,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<Vec<DownloadFile>, AppError> {
+    let client = aria2c_state.client()?;
+
+    let files = client.get_files(&gid).await?;
+    let select_file = file_selection.resolve(&files)?;
+
+    let mut options = HashMap::new();
+    options.insert("select-file".to_string(), select_file);
+    client.change_option(&gid, options).await?;
+
+    client.get_files(&gid).await
+}
+
 // Tauri命令：获取BT任务的伙伴信息
 #[tauri::command]
-pub async fn get_peers(gid: String) -> Result<Vec<PeerInfo>, String> {
-    let client = Aria2cClient::new();
+pub async fn get_peers(
+    gid: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<Vec<PeerInfo>, AppError> {
+    let client = aria2c_state.client()?;
     client.get_peers(&gid).await
 }
 
 #[tauri::command]
-pub async fn get_files(gid: String) -> Result<Vec<DownloadFile>, String> {
-    let client = Aria2cClient::new();
+pub async fn get_files(
+    gid: String,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<Vec<DownloadFile>, AppError> {
+    let client = aria2c_state.client()?;
     client.get_files(&gid).await
 }
 
 // Tauri命令：获取全局统计信息
 #[tauri::command]
-pub async fn get_download_stats() -> Result<serde_json::Value, String> {
-    let client = Aria2cClient::new();
+pub async fn get_download_stats(
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<serde_json::Value, AppError> {
+    let client = aria2c_state.client()?;
     client.get_global_stat().await
 }
 
@@ -214,15 +571,18 @@ pub async fn get_download_stats() -> Result<serde_json::Value, String> {
 #[tauri::command]
 pub async fn add_batch_downloads(
     download_list: Vec<serde_json::Value>,
-) -> Result<Vec<String>, String> {
-    let client = Aria2cClient::new();
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    torrent_cache: tauri::State<'_, TorrentCache>,
+) -> Result<Vec<String>, AppError> {
+    let client = aria2c_state.client()?;
     let mut gids = Vec::new();
 
     for download_item in download_list {
         let download_type = download_item
             .get("type")
             .and_then(|v| v.as_str())
-            .ok_or("Missing download type")?;
+            .ok_or_else(|| AppError::InvalidInput("Missing download type".to_string()))?;
 
         let download_dir = download_item
             .get("dir")
@@ -234,25 +594,21 @@ pub async fn add_batch_downloads(
                 let urls = download_item
                     .get("urls")
                     .and_then(|v| v.as_array())
-                    .ok_or("Missing URLs for URL download")?
+                    .ok_or_else(|| {
+                        AppError::InvalidInput("Missing URLs for URL download".to_string())
+                    })?
                     .iter()
                     .filter_map(|v| v.as_str())
                     .map(|s| s.to_string())
                     .collect::<Vec<String>>();
 
                 if urls.is_empty() {
-                    return Err("No valid URLs provided".to_string());
+                    return Err(AppError::InvalidInput("No valid URLs provided".to_string()));
                 }
 
-                let mut options = HashMap::new();
-                if let Some(dir) = download_dir {
-                    options.insert("dir".to_string(), dir);
-                }
-                let options = if options.is_empty() {
-                    None
-                } else {
-                    Some(options)
-                };
+                let options = settings_state
+                    .lock()?
+                    .category_options_with_dir(&TaskDescriptor::from_uris(&urls), download_dir);
 
                 client.add_uri(urls, options).await?
             }
@@ -260,18 +616,14 @@ pub async fn add_batch_downloads(
                 let magnet = download_item
                     .get("magnet")
                     .and_then(|v| v.as_str())
-                    .ok_or("Missing magnet link")?
+                    .ok_or_else(|| AppError::InvalidInput("Missing magnet link".to_string()))?
                     .to_string();
+                let magnet_link = MagnetLink::parse(&magnet)?;
 
-                let mut options = HashMap::new();
-                if let Some(dir) = download_dir {
-                    options.insert("dir".to_string(), dir);
-                }
-                let options = if options.is_empty() {
-                    None
-                } else {
-                    Some(options)
-                };
+                let options = settings_state.lock()?.category_options_with_dir(
+                    &TaskDescriptor::from_magnet(&magnet_link),
+                    download_dir,
+                );
 
                 client.add_uri(vec![magnet], options).await?
             }
@@ -279,25 +631,50 @@ pub async fn add_batch_downloads(
                 let torrent_path = download_item
                     .get("torrent_path")
                     .and_then(|v| v.as_str())
-                    .ok_or("Missing torrent file path")?;
+                    .ok_or_else(|| {
+                        AppError::InvalidInput("Missing torrent file path".to_string())
+                    })?;
 
-                let torrent_data = tokio::fs::read(torrent_path)
-                    .await
-                    .map_err(|e| format!("Failed to read torrent file: {}", e))?;
+                let torrent_data = tokio::fs::read(torrent_path).await?;
+                let meta = torrent_cache.store(&torrent_data)?;
 
-                let mut options = HashMap::new();
-                if let Some(dir) = download_dir {
-                    options.insert("dir".to_string(), dir);
-                }
-                let options = if options.is_empty() {
-                    None
+                let options = settings_state
+                    .lock()?
+                    .category_options_with_dir(&TaskDescriptor::from_torrent(&meta), download_dir);
+
+                client.add_torrent(torrent_data, None, options).await?
+            }
+            "metalink" => {
+                // 支持文件路径或 Base64 内容
+                let metalink_data = if let Some(path) =
+                    download_item.get("metalink_path").and_then(|v| v.as_str())
+                {
+                    tokio::fs::read(path).await?
+                } else if let Some(content) = download_item
+                    .get("metalink_base64")
+                    .and_then(|v| v.as_str())
+                {
+                    base64::engine::general_purpose::STANDARD.decode(content)?
                 } else {
-                    Some(options)
+                    return Err(AppError::InvalidInput(
+                        "Missing metalink file path or content".to_string(),
+                    ));
                 };
 
-                client.add_torrent(torrent_data, None, options).await?
+                let options = settings_state
+                    .lock()?
+                    .category_options_with_dir(&TaskDescriptor::metalink(), download_dir);
+
+                // 一个 Metalink 会创建多个任务
+                gids.extend(client.add_metalink(metalink_data, options).await?);
+                continue;
+            }
+            _ => {
+                return Err(AppError::InvalidInput(format!(
+                    "Unsupported download type: {}",
+                    download_type
+                )))
             }
-            _ => return Err(format!("Unsupported download type: {}", download_type)),
         };
 
         gids.push(gid);
@@ -308,8 +685,11 @@ pub async fn add_batch_downloads(
 
 // Tauri命令：测试aria2c连接并获取详细信息
 #[tauri::command]
-pub async fn test_aria2c_connection_detailed() -> Result<serde_json::Value, String> {
-    let client = Aria2cClient::new();
+pub async fn test_aria2c_connection_detailed(
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<serde_json::Value, AppError> {
+    let endpoint = aria2c_state.endpoint()?;
+    let client = Aria2cClient::new(&endpoint);
 
     // 首先测试基本连接
     let global_stat = match client.get_global_stat().await {
@@ -318,16 +698,22 @@ pub async fn test_aria2c_connection_detailed() -> Result<serde_json::Value, Stri
             return Ok(serde_json::json!({
                 "connected": false,
                 "error": e,
-                "rpc_url": "http://127.0.0.1:6800/jsonrpc",
-                "rpc_secret": "game_app_secret_2024"
+                "rpc_url": endpoint.http_url(),
+                "rpc_secret": endpoint.secret
             }));
         }
     };
 
     // 获取活动任务
     let active_tasks = client.get_active_downloads().await.unwrap_or_default();
-    let waiting_tasks = client.get_waiting_downloads().await.unwrap_or_default();
-    let stopped_tasks = client.get_stopped_downloads().await.unwrap_or_default();
+    let waiting_tasks = client
+        .get_waiting_downloads(0, DEFAULT_PAGE_SIZE)
+        .await
+        .unwrap_or_default();
+    let stopped_tasks = client
+        .get_stopped_downloads(0, DEFAULT_PAGE_SIZE)
+        .await
+        .unwrap_or_default();
 
     Ok(serde_json::json!({
         "connected": true,
@@ -345,17 +731,15 @@ pub async fn test_aria2c_connection_detailed() -> Result<serde_json::Value, Stri
 
 // Tauri命令：测试aria2c连接
 #[tauri::command]
-pub async fn test_aria2c_connection() -> Result<bool, String> {
-    let client = Aria2cClient::new();
+pub async fn test_aria2c_connection(
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<bool, AppError> {
+    let client = aria2c_state.client()?;
     match client.get_global_stat().await {
         Ok(_) => Ok(true),
-        Err(e) => {
-            if e.contains("Connection refused") || e.contains("HTTP request failed") {
-                Ok(false)
-            } else {
-                Err(e)
-            }
-        }
+        // 无法建立连接说明守护进程不在线，其他错误（如密钥错误）需要交给前端处理
+        Err(AppError::Transport(_)) => Ok(false),
+        Err(e) => Err(e),
     }
 }
 
@@ -364,18 +748,13 @@ pub async fn test_aria2c_connection() -> Result<bool, String> {
 pub async fn add_download_url_simple(
     urls: Vec<String>,
     download_dir: Option<String>,
-) -> Result<String, String> {
-    let client = Aria2cClient::new();
-    let mut options = HashMap::new();
-    if let Some(dir) = download_dir {
-        options.insert("dir".to_string(), dir);
-    }
-
-    let options = if options.is_empty() {
-        None
-    } else {
-        Some(options)
-    };
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
+    let options = settings_state
+        .lock()?
+        .category_options_with_dir(&TaskDescriptor::from_uris(&urls), download_dir);
 
     client.add_uri(urls, options).await
 }
@@ -384,22 +763,17 @@ pub async fn add_download_url_simple(
 pub async fn add_download_torrent_simple(
     torrent_path: String,
     download_dir: Option<String>,
-) -> Result<String, String> {
-    let client = Aria2cClient::new();
-    let torrent_data = tokio::fs::read(&torrent_path)
-        .await
-        .map_err(|e| format!("Failed to read torrent file: {}", e))?;
-
-    let mut options = HashMap::new();
-    if let Some(dir) = download_dir {
-        options.insert("dir".to_string(), dir);
-    }
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    torrent_cache: tauri::State<'_, TorrentCache>,
+) -> Result<String, AppError> {
+    let client = aria2c_state.client()?;
+    let torrent_data = tokio::fs::read(&torrent_path).await?;
+    let meta = torrent_cache.store(&torrent_data)?;
 
-    let options = if options.is_empty() {
-        None
-    } else {
-        Some(options)
-    };
+    let options = settings_state
+        .lock()?
+        .category_options_with_dir(&TaskDescriptor::from_torrent(&meta), download_dir);
 
     client.add_torrent(torrent_data, None, options).await
 }
@@ -408,34 +782,35 @@ pub async fn add_download_torrent_simple(
 pub async fn add_download_magnet_simple(
     magnet_link: String,
     download_dir: Option<String>,
-) -> Result<String, String> {
-    let client = Aria2cClient::new();
-
-    let mut options = HashMap::new();
-    if let Some(dir) = download_dir {
-        options.insert("dir".to_string(), dir);
-    }
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<String, AppError> {
+    let magnet = MagnetLink::parse(&magnet_link)?;
+    let client = aria2c_state.client()?;
 
-    let options = if options.is_empty() {
-        None
-    } else {
-        Some(options)
-    };
+    let options = settings_state
+        .lock()?
+        .category_options_with_dir(&TaskDescriptor::from_magnet(&magnet), download_dir);
 
     client.add_uri(vec![magnet_link], options).await
 }
 
 // Tauri命令：获取全局选项
 #[tauri::command]
-pub async fn get_global_options() -> Result<HashMap<String, String>, String> {
-    let client = Aria2cClient::new();
+pub async fn get_global_options(
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<HashMap<String, String>, AppError> {
+    let client = aria2c_state.client()?;
     client.get_global_option().await
 }
 
 // Tauri命令：更改全局选项
 #[tauri::command]
-pub async fn change_global_option(options: HashMap<String, String>) -> Result<(), String> {
-    let client = Aria2cClient::new();
+pub async fn change_global_option(
+    options: HashMap<String, String>,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+) -> Result<(), AppError> {
+    let client = aria2c_state.client()?;
     println!("change_global_option: {:?}", options);
     client.change_global_option(options).await
 }
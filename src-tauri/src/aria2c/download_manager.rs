@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -22,6 +23,8 @@ pub struct DownloadTask {
     #[serde(rename = "errorMessage")]
     pub error_message: Option<String>,
     pub bittorrent: Option<BitTorrentInfo>,
+    #[serde(rename = "infoHash")]
+    pub info_hash: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,6 +94,258 @@ pub struct PeerInfo {
     pub seeder: bool,
 }
 
+/// 拉取完整列表时每批请求的任务数
+const LIST_BATCH_SIZE: u64 = 1000;
+
+/// 列表查询的基本字段
+const LIST_QUERY_KEYS: [&str; 10] = [
+    "gid",
+    "status",
+    "totalLength",
+    "completedLength",
+    "downloadSpeed",
+    "uploadSpeed",
+    "dir",
+    "errorCode",
+    "errorMessage",
+    "infoHash",
+];
+
+/// aria2 的三个任务列表
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskSource {
+    Active,
+    Waiting,
+    Stopped,
+}
+
+/// 等待队列中的移动方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum QueueMove {
+    /// 移到队首
+    Top,
+    /// 移到队尾
+    Bottom,
+    /// 移到指定位置 (从 0 开始)
+    Absolute { position: i64 },
+    /// 相对当前位置移动，负数向前
+    Relative { offset: i64 },
+}
+
+impl QueueMove {
+    /// 转换为 aria2.changePosition 的 (pos, how) 参数
+    fn to_position(self) -> (i64, &'static str) {
+        match self {
+            QueueMove::Top => (0, "POS_SET"),
+            QueueMove::Bottom => (0, "POS_END"),
+            QueueMove::Absolute { position } => (position, "POS_SET"),
+            QueueMove::Relative { offset } => (offset, "POS_CUR"),
+        }
+    }
+}
+
+/// 任务类型：BT（种子/磁力）或普通 HTTP/FTP 下载
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    Torrent,
+    Http,
+}
+
+/// 任务列表的过滤条件，在 Rust 侧执行
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+    /// aria2 状态：active/waiting/paused/complete/error/removed
+    pub status: Option<String>,
+    /// 名称包含的关键字，不区分大小写
+    pub name: Option<String>,
+    /// 保存目录（包含子目录）
+    pub dir: Option<String>,
+    pub kind: Option<TaskKind>,
+}
+
+impl TaskFilter {
+    fn is_empty(&self) -> bool {
+        self.status.is_none() && self.name.is_none() && self.dir.is_none() && self.kind.is_none()
+    }
+
+    pub fn matches(&self, task: &DownloadTask) -> bool {
+        if let Some(status) = &self.status {
+            if &task.status != status {
+                return false;
+            }
+        }
+
+        if let Some(kind) = self.kind {
+            let is_torrent = task.info_hash.is_some() || task.bittorrent.is_some();
+            if is_torrent != (kind == TaskKind::Torrent) {
+                return false;
+            }
+        }
+
+        if let Some(dir) = &self.dir {
+            let in_dir = task
+                .dir
+                .as_deref()
+                .is_some_and(|task_dir| std::path::Path::new(task_dir).starts_with(dir));
+            if !in_dir {
+                return false;
+            }
+        }
+
+        if let Some(name) = &self.name {
+            let keyword = name.to_lowercase();
+            if !task.name().to_lowercase().contains(&keyword) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// 分页后的任务列表，`total` 为过滤后的总数
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskPage {
+    pub tasks: Vec<DownloadTask>,
+    pub total: u64,
+}
+
+impl TaskPage {
+    fn from_filtered(
+        tasks: Vec<DownloadTask>,
+        filter: &TaskFilter,
+        offset: u64,
+        limit: u64,
+    ) -> Self {
+        let matched: Vec<DownloadTask> = tasks
+            .into_iter()
+            .filter(|task| filter.matches(task))
+            .collect();
+        let total = matched.len() as u64;
+        let tasks = matched
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
+        Self { tasks, total }
+    }
+}
+
+/// 列表查询的字段，按名称过滤时额外请求文件和种子信息
+fn list_query_keys(filter: Option<&TaskFilter>) -> serde_json::Value {
+    let mut keys: Vec<&str> = LIST_QUERY_KEYS.to_vec();
+    if filter.is_some_and(|f| f.name.is_some()) {
+        keys.push("files");
+        keys.push("bittorrent");
+    }
+    json!(keys)
+}
+
+impl DownloadTask {
+    /// 任务名称：BT 任务取种子名，否则取第一个文件名，再否则取第一个地址
+    pub fn name(&self) -> String {
+        let torrent_name = self
+            .bittorrent
+            .as_ref()
+            .and_then(|bt| bt.info.as_ref())
+            .and_then(|info| info.get("name"))
+            .and_then(|name| name.as_str());
+        if let Some(name) = torrent_name {
+            return name.to_string();
+        }
+
+        let Some(file) = self.files.first() else {
+            return self.gid.clone();
+        };
+        if !file.path.is_empty() {
+            return std::path::Path::new(&file.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| file.path.clone());
+        }
+        file.uris
+            .first()
+            .map(|uri| uri.uri.clone())
+            .unwrap_or_else(|| self.gid.clone())
+    }
+}
+
+/// aria2c RPC 连接参数
+#[derive(Debug, Clone)]
+pub struct RpcEndpoint {
+    /// 是否使用 TLS (https/wss)
+    pub secure: bool,
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+    pub secret: String,
+    /// 是否为外部守护进程（不由本程序启动和管理）
+    pub external: bool,
+}
+
+impl RpcEndpoint {
+    /// 本地 sidecar 的连接参数
+    pub fn new(port: u16, secret: String) -> Self {
+        Self {
+            secure: false,
+            host: "127.0.0.1".to_string(),
+            port,
+            path: "/jsonrpc".to_string(),
+            secret,
+            external: false,
+        }
+    }
+
+    /// 解析用户提供的外部守护进程地址，支持 http/https/ws/wss
+    pub fn external(rpc_url: &str, secret: String) -> Result<Self, AppError> {
+        let url = url::Url::parse(rpc_url.trim())
+            .map_err(|e| AppError::InvalidInput(format!("Invalid RPC URL {}: {}", rpc_url, e)))?;
+
+        let secure = match url.scheme() {
+            "http" | "ws" => false,
+            "https" | "wss" => true,
+            scheme => {
+                return Err(AppError::InvalidInput(format!(
+                    "Unsupported RPC URL scheme: {}",
+                    scheme
+                )))
+            }
+        };
+        let host = url
+            .host_str()
+            .ok_or_else(|| AppError::InvalidInput(format!("RPC URL has no host: {}", rpc_url)))?
+            .to_string();
+        let port = url.port_or_known_default().unwrap_or(6800);
+        let path = match url.path() {
+            "" | "/" => "/jsonrpc".to_string(),
+            path => path.to_string(),
+        };
+
+        Ok(Self {
+            secure,
+            host,
+            port,
+            path,
+            secret,
+            external: true,
+        })
+    }
+
+    /// HTTP JSON-RPC 地址
+    pub fn http_url(&self) -> String {
+        let scheme = if self.secure { "https" } else { "http" };
+        format!("{}://{}:{}{}", scheme, self.host, self.port, self.path)
+    }
+
+    /// WebSocket JSON-RPC 地址，用于接收通知
+    pub fn ws_url(&self) -> String {
+        let scheme = if self.secure { "wss" } else { "ws" };
+        format!("{}://{}:{}{}", scheme, self.host, self.port, self.path)
+    }
+}
+
 pub struct Aria2cClient {
     client: reqwest::Client,
     rpc_url: String,
@@ -98,11 +353,11 @@ pub struct Aria2cClient {
 }
 
 impl Aria2cClient {
-    pub fn new() -> Self {
+    pub fn new(endpoint: &RpcEndpoint) -> Self {
         Self {
             client: reqwest::Client::new(),
-            rpc_url: "http://127.0.0.1:6800/jsonrpc".to_string(),
-            rpc_secret: "game_app_secret_2024".to_string(),
+            rpc_url: endpoint.http_url(),
+            rpc_secret: endpoint.secret.clone(),
         }
     }
 
@@ -110,7 +365,7 @@ impl Aria2cClient {
         &self,
         method: &str,
         params: Vec<serde_json::Value>,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, AppError> {
         let mut rpc_params = vec![serde_json::Value::String(format!(
             "token:{}",
             self.rpc_secret
@@ -124,43 +379,34 @@ impl Aria2cClient {
             params: rpc_params,
         };
 
-        let response = self
-            .client
-            .post(&self.rpc_url)
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
+        let response = self.client.post(&self.rpc_url).json(&request).send().await?;
 
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| format!("Failed to get response text: {}", e))?;
+        let response_text = response.text().await?;
 
         let rpc_response: JsonRpcResponse = serde_json::from_str(&response_text).map_err(|e| {
-            format!(
-                "Failed to parse JSON response: {} - 响应内容: {}",
+            AppError::Parse(format!(
+                "Failed to parse JSON-RPC response: {} - response: {}",
                 e, response_text
-            )
+            ))
         })?;
 
         if let Some(error) = rpc_response.error {
-            return Err(format!(
-                "RPC error: {} (code: {})",
-                error.message, error.code
-            ));
+            return Err(AppError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
         }
 
         rpc_response
             .result
-            .ok_or_else(|| "No result in response".to_string())
+            .ok_or_else(|| AppError::Parse("No result in JSON-RPC response".to_string()))
     }
 
     pub async fn add_uri(
         &self,
         uris: Vec<String>,
         options: Option<HashMap<String, String>>,
-    ) -> Result<String, String> {
+    ) -> Result<String, AppError> {
         let mut params = vec![serde_json::Value::Array(
             uris.into_iter().map(serde_json::Value::String).collect(),
         )];
@@ -177,7 +423,7 @@ impl Aria2cClient {
 
         result
             .as_str()
-            .ok_or_else(|| "Invalid GID returned".to_string())
+            .ok_or_else(|| AppError::Parse("Invalid GID returned".to_string()))
             .map(|s| s.to_string())
     }
 
@@ -186,7 +432,7 @@ impl Aria2cClient {
         torrent_data: Vec<u8>,
         uris: Option<Vec<String>>,
         options: Option<HashMap<String, String>>,
-    ) -> Result<String, String> {
+    ) -> Result<String, AppError> {
         let torrent_base64 = base64::engine::general_purpose::STANDARD.encode(&torrent_data);
 
         let mut params = vec![serde_json::Value::String(torrent_base64)];
@@ -214,12 +460,51 @@ impl Aria2cClient {
 
         result
             .as_str()
-            .ok_or_else(|| "Invalid GID returned".to_string())
+            .ok_or_else(|| AppError::Parse("Invalid GID returned".to_string()))
             .map(|s| s.to_string())
     }
 
+    /// 添加 Metalink 任务，一个 Metalink 文件可能包含多个文件，返回每个文件对应的 GID
+    pub async fn add_metalink(
+        &self,
+        metalink_data: Vec<u8>,
+        options: Option<HashMap<String, String>>,
+    ) -> Result<Vec<String>, AppError> {
+        let metalink_base64 = base64::engine::general_purpose::STANDARD.encode(&metalink_data);
+
+        let mut params = vec![serde_json::Value::String(metalink_base64)];
+
+        if let Some(opts) = options {
+            params.push(serde_json::Value::Object(
+                opts.into_iter()
+                    .map(|(k, v)| (k, serde_json::Value::String(v)))
+                    .collect(),
+            ));
+        }
+
+        let result = self.make_rpc_call("aria2.addMetalink", params).await?;
+
+        let gids = result
+            .as_array()
+            .ok_or_else(|| AppError::Parse("Invalid GID list returned".to_string()))?
+            .iter()
+            .map(|gid| {
+                gid.as_str()
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| AppError::Parse("Invalid GID returned".to_string()))
+            })
+            .collect::<Result<Vec<String>, AppError>>()?;
+
+        if gids.is_empty() {
+            return Err(AppError::InvalidInput(
+                "Metalink does not contain any downloadable file".to_string(),
+            ));
+        }
+        Ok(gids)
+    }
+
     // 解析任务数据的辅助方法
-    async fn parse_task_data(&self, task_data: serde_json::Value) -> Result<DownloadTask, String> {
+    async fn parse_task_data(&self, task_data: serde_json::Value) -> Result<DownloadTask, AppError> {
         // 手动解析以应对不同的字段名和缺失字段
         let gid = task_data
             .get("gid")
@@ -309,6 +594,10 @@ impl Aria2cClient {
         let bittorrent = task_data
             .get("bittorrent")
             .and_then(|v| serde_json::from_value(v.clone()).ok());
+        let info_hash = task_data
+            .get("infoHash")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
 
         Ok(DownloadTask {
             gid,
@@ -322,10 +611,11 @@ impl Aria2cClient {
             error_code,
             error_message,
             bittorrent,
+            info_hash,
         })
     }
 
-    pub async fn get_download_status(&self, gid: &str) -> Result<DownloadTask, String> {
+    pub async fn get_download_status(&self, gid: &str) -> Result<DownloadTask, AppError> {
         let params = vec![serde_json::Value::String(gid.to_string())];
         let result = self.make_rpc_call("aria2.tellStatus", params).await?;
 
@@ -344,7 +634,7 @@ impl Aria2cClient {
         &self,
         gid: &str,
         keys: Option<Vec<String>>,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, AppError> {
         let mut params = vec![serde_json::Value::String(gid.to_string())];
 
         // 如果指定了字段列表，会传递给aria2.tellStatus作为第二个参数
@@ -361,25 +651,17 @@ impl Aria2cClient {
         self.make_rpc_call("aria2.tellStatus", params).await
     }  
 
-    
-    pub async fn get_active_downloads(&self) -> Result<Vec<DownloadTask>, String> {
-        let query_item = json!([
-            "gid",
-            "status",
-            "totalLength",
-            "completedLength",
-            "downloadSpeed",
-            "totalLength",
-            "dir",
-            "errorCode",
-            // "bittorrent",
-            "errorMessage"
-        ]);
-        let result = self
-            .make_rpc_call("aria2.tellActive", vec![query_item])
-            .await?;
+    /// 请求任务列表并逐个解析，解析失败的任务会被跳过
+    async fn fetch_tasks(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<Vec<DownloadTask>, AppError> {
+        let result = self.make_rpc_call(method, params).await?;
 
-        let tasks = result.as_array().ok_or("Expected array of tasks")?;
+        let tasks = result
+            .as_array()
+            .ok_or_else(|| AppError::Parse("Expected array of tasks".to_string()))?;
 
         let mut download_tasks = Vec::new();
 
@@ -387,7 +669,7 @@ impl Aria2cClient {
             match self.parse_task_data(task_data.clone()).await {
                 Ok(task) => download_tasks.push(task),
                 Err(e) => {
-                    println!("跳过解析失败的任务: {}", e);
+                    println!("跳过解析失败的任务 ({}): {}", method, e);
                 }
             }
         }
@@ -395,107 +677,167 @@ impl Aria2cClient {
         Ok(download_tasks)
     }
 
-    pub async fn get_waiting_downloads(&self) -> Result<Vec<DownloadTask>, String> {
-        let query_item = json!([
-            "gid",
-            "status",
-            "totalLength",
-            "completedLength",
-            "downloadSpeed",
-            "totalLength",
-            "dir",
-            "errorCode",
-            // "bittorrent",
-            "errorMessage"
-        ]);
-        let result = self
-            .make_rpc_call(
-                "aria2.tellWaiting",
-                vec![
-                    serde_json::Value::Number(serde_json::Number::from(0)),
-                    serde_json::Value::Number(serde_json::Number::from(100)),
-                    query_item,
-                ],
-            )
-            .await?;
-
-        let tasks = result.as_array().ok_or("Expected array of tasks")?;
-
-        let mut download_tasks = Vec::new();
+    /// 获取一个列表中的全部任务，等待/已停止列表按批次请求
+    async fn fetch_all_tasks(
+        &self,
+        source: TaskSource,
+        keys: &serde_json::Value,
+    ) -> Result<Vec<DownloadTask>, AppError> {
+        let method = match source {
+            TaskSource::Active => {
+                return self
+                    .fetch_tasks("aria2.tellActive", vec![keys.clone()])
+                    .await;
+            }
+            TaskSource::Waiting => "aria2.tellWaiting",
+            TaskSource::Stopped => "aria2.tellStopped",
+        };
 
-        for task_data in tasks {
-            match self.parse_task_data(task_data.clone()).await {
-                Ok(task) => download_tasks.push(task),
-                Err(e) => {
-                    println!("跳过解析失败的等待任务: {}", e);
-                }
+        let mut all_tasks = Vec::new();
+        loop {
+            let batch = self
+                .fetch_tasks(
+                    method,
+                    vec![json!(all_tasks.len()), json!(LIST_BATCH_SIZE), keys.clone()],
+                )
+                .await?;
+            let done = (batch.len() as u64) < LIST_BATCH_SIZE;
+            all_tasks.extend(batch);
+            if done {
+                return Ok(all_tasks);
             }
         }
-
-        Ok(download_tasks)
     }
 
-    pub async fn get_stopped_downloads(&self) -> Result<Vec<DownloadTask>, String> {
-        let query_item = json!([
-            "gid",
-            "status",
-            "totalLength",
-            "completedLength",
-            "downloadSpeed",
-            "totalLength",
-            "dir",
-            "errorCode",
-            // "bittorrent",
-            "errorMessage"
-        ]);
-        let result = self
-            .make_rpc_call(
-                "aria2.tellStopped",
-                vec![
-                    serde_json::Value::Number(serde_json::Number::from(0)),
-                    serde_json::Value::Number(serde_json::Number::from(100)),
-                    query_item,
-                ],
-            )
-            .await?;
+    pub async fn get_active_downloads(&self) -> Result<Vec<DownloadTask>, AppError> {
+        self.fetch_tasks("aria2.tellActive", vec![list_query_keys(None)])
+            .await
+    }
 
-        let tasks = result.as_array().ok_or("Expected array of tasks")?;
+    pub async fn get_waiting_downloads(
+        &self,
+        offset: u64,
+        num: u64,
+    ) -> Result<Vec<DownloadTask>, AppError> {
+        self.fetch_tasks(
+            "aria2.tellWaiting",
+            vec![json!(offset), json!(num), list_query_keys(None)],
+        )
+        .await
+    }
 
-        let mut download_tasks = Vec::new();
+    pub async fn get_stopped_downloads(
+        &self,
+        offset: u64,
+        num: u64,
+    ) -> Result<Vec<DownloadTask>, AppError> {
+        self.fetch_tasks(
+            "aria2.tellStopped",
+            vec![json!(offset), json!(num), list_query_keys(None)],
+        )
+        .await
+    }
 
-        for task_data in tasks {
-            match self.parse_task_data(task_data.clone()).await {
-                Ok(task) => download_tasks.push(task),
-                Err(e) => {
-                    println!("跳过解析失败的停止任务: {}", e);
+    /// 分页获取一个列表中的任务，指定过滤条件时在 Rust 侧过滤后再分页
+    pub async fn list_tasks(
+        &self,
+        source: TaskSource,
+        offset: u64,
+        limit: u64,
+        filter: Option<&TaskFilter>,
+    ) -> Result<TaskPage, AppError> {
+        let filter = filter.filter(|f| !f.is_empty());
+
+        let Some(filter) = filter else {
+            // 无过滤条件时直接使用 aria2 的分页，总数来自 getGlobalStat
+            let stat = self.get_global_stat().await?;
+            let count = |key: &str| {
+                stat.get(key)
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .unwrap_or(0)
+            };
+            let (tasks, total) = match source {
+                TaskSource::Active => {
+                    let tasks = self.get_active_downloads().await?;
+                    let total = tasks.len() as u64;
+                    let tasks = tasks
+                        .into_iter()
+                        .skip(offset as usize)
+                        .take(limit as usize)
+                        .collect();
+                    (tasks, total)
                 }
-            }
+                TaskSource::Waiting => (
+                    self.get_waiting_downloads(offset, limit).await?,
+                    count("numWaiting"),
+                ),
+                TaskSource::Stopped => (
+                    self.get_stopped_downloads(offset, limit).await?,
+                    count("numStopped"),
+                ),
+            };
+            return Ok(TaskPage { tasks, total });
+        };
+
+        let tasks = self
+            .fetch_all_tasks(source, &list_query_keys(Some(filter)))
+            .await?;
+        Ok(TaskPage::from_filtered(tasks, filter, offset, limit))
+    }
+
+    /// 合并活动、等待和已停止的任务，过滤后分页
+    pub async fn list_all_tasks(
+        &self,
+        offset: u64,
+        limit: u64,
+        filter: Option<&TaskFilter>,
+    ) -> Result<TaskPage, AppError> {
+        let default_filter = TaskFilter::default();
+        let filter = filter.unwrap_or(&default_filter);
+        let keys = list_query_keys(Some(filter));
+
+        let mut tasks = Vec::new();
+        for source in [TaskSource::Active, TaskSource::Waiting, TaskSource::Stopped] {
+            tasks.extend(self.fetch_all_tasks(source, &keys).await?);
         }
 
-        Ok(download_tasks)
+        Ok(TaskPage::from_filtered(tasks, filter, offset, limit))
     }
 
-    pub async fn pause_download(&self, gid: &str) -> Result<String, String> {
+    pub async fn pause_download(&self, gid: &str) -> Result<String, AppError> {
         let params = vec![serde_json::Value::String(gid.to_string())];
         let result = self.make_rpc_call("aria2.forcePause", params).await?;
 
         result
             .as_str()
-            .ok_or_else(|| "Invalid response".to_string())
+            .ok_or_else(|| AppError::Parse("Invalid response".to_string()))
             .map(|s| s.to_string())
     }
 
-    pub async fn unpause_download(&self, gid: &str) -> Result<String, String> {
+    pub async fn unpause_download(&self, gid: &str) -> Result<String, AppError> {
         let params = vec![serde_json::Value::String(gid.to_string())];
         let result = self.make_rpc_call("aria2.unpause", params).await?;
 
         result
             .as_str()
-            .ok_or_else(|| "Invalid response".to_string())
+            .ok_or_else(|| AppError::Parse("Invalid response".to_string()))
             .map(|s| s.to_string())
     }
 
-    pub async fn remove_download(&self, gid: &str) -> Result<String, String> {
+    /// 暂停全部活动和等待中的任务
+    pub async fn force_pause_all(&self) -> Result<(), AppError> {
+        self.make_rpc_call("aria2.forcePauseAll", vec![]).await?;
+        Ok(())
+    }
+
+    /// 恢复全部暂停的任务
+    pub async fn unpause_all(&self) -> Result<(), AppError> {
+        self.make_rpc_call("aria2.unpauseAll", vec![]).await?;
+        Ok(())
+    }
+
+    pub async fn remove_download(&self, gid: &str) -> Result<String, AppError> {
         // 首先尝试使用 aria2.remove (用于活动或等待中的任务)
         println!("删除任务: {}", gid);
         let params = vec![serde_json::Value::String(gid.to_string())];
@@ -505,7 +847,7 @@ impl Aria2cClient {
         {
             Ok(result) => result
                 .as_str()
-                .ok_or_else(|| "Invalid response".to_string())
+                .ok_or_else(|| AppError::Parse("Invalid response".to_string()))
                 .map(|s| s.to_string()),
             Err(_) => {
                 // 如果 aria2.remove 失败，尝试使用 aria2.removeDownloadResult (用于已完成的任务)
@@ -515,7 +857,7 @@ impl Aria2cClient {
                 {
                     Ok(result) => result
                         .as_str()
-                        .ok_or_else(|| "Invalid response".to_string())
+                        .ok_or_else(|| AppError::Parse("Invalid response".to_string()))
                         .map(|s| s.to_string()),
                     Err(e) => Err(e),
                 }
@@ -523,25 +865,39 @@ impl Aria2cClient {
         }
     }
 
+    /// 强制停止活动或等待中的任务，任务会移到已停止列表
+    pub async fn force_remove(&self, gid: &str) -> Result<(), AppError> {
+        self.make_rpc_call("aria2.forceRemove", vec![json!(gid)])
+            .await?;
+        Ok(())
+    }
+
+    /// 从已停止列表中移除任务记录
+    pub async fn remove_download_result(&self, gid: &str) -> Result<(), AppError> {
+        self.make_rpc_call("aria2.removeDownloadResult", vec![json!(gid)])
+            .await?;
+        Ok(())
+    }
+
     /// 清理已完成/错误/已删除的下载任务
-    pub async fn purge_download_result(&self) -> Result<String, String> {
+    pub async fn purge_download_result(&self) -> Result<String, AppError> {
         let result = self
             .make_rpc_call("aria2.purgeDownloadResult", vec![])
             .await?;
 
         result
             .as_str()
-            .ok_or_else(|| "Invalid response".to_string())
+            .ok_or_else(|| AppError::Parse("Invalid response".to_string()))
             .map(|s| s.to_string())
     }
 
     /// 获取BT任务的伙伴信息
     /// 获取任务的文件列表
-    pub async fn get_files(&self, gid: &str) -> Result<Vec<DownloadFile>, String> {
+    pub async fn get_files(&self, gid: &str) -> Result<Vec<DownloadFile>, AppError> {
         let params = vec![serde_json::Value::String(gid.to_string())];
         let result = self.make_rpc_call("aria2.getFiles", params).await?;
 
-        let files = result.as_array().ok_or("Expected array of files")?;
+        let files = result.as_array().ok_or_else(|| AppError::Parse("Expected array of files".to_string()))?;
 
         let mut download_files = Vec::new();
 
@@ -600,12 +956,12 @@ impl Aria2cClient {
         Ok(download_files)
     }
 
-    pub async fn get_peers(&self, gid: &str) -> Result<Vec<PeerInfo>, String> {
+    pub async fn get_peers(&self, gid: &str) -> Result<Vec<PeerInfo>, AppError> {
         let params = vec![serde_json::Value::String(gid.to_string())];
         let result = self.make_rpc_call("aria2.getPeers", params).await?;
 
         // 解析伙伴信息
-        let peers = result.as_array().ok_or("Expected array of peers")?;
+        let peers = result.as_array().ok_or_else(|| AppError::Parse("Expected array of peers".to_string()))?;
 
         let mut peer_infos = Vec::new();
 
@@ -661,18 +1017,92 @@ impl Aria2cClient {
         Ok(peer_infos)
     }
 
-    pub async fn get_global_stat(&self) -> Result<serde_json::Value, String> {
+    /// 立即保存会话文件，返回后会话已写入磁盘
+    pub async fn save_session(&self) -> Result<(), AppError> {
+        self.make_rpc_call("aria2.saveSession", vec![]).await?;
+        Ok(())
+    }
+
+    /// 请求 aria2 正常退出（会先停止所有任务并保存会话）
+    pub async fn shutdown(&self) -> Result<(), AppError> {
+        self.make_rpc_call("aria2.shutdown", vec![]).await?;
+        Ok(())
+    }
+
+    /// 获取 aria2 版本信息，也用于验证地址和密钥是否正确
+    pub async fn get_version(&self) -> Result<serde_json::Value, AppError> {
+        self.make_rpc_call("aria2.getVersion", vec![]).await
+    }
+
+    pub async fn get_global_stat(&self) -> Result<serde_json::Value, AppError> {
         self.make_rpc_call("aria2.getGlobalStat", vec![]).await
     }
 
+    /// 在等待队列中移动任务，返回移动后的位置
+    pub async fn change_position(&self, gid: &str, to: QueueMove) -> Result<u64, AppError> {
+        let (pos, how) = to.to_position();
+        let result = self
+            .make_rpc_call(
+                "aria2.changePosition",
+                vec![json!(gid), json!(pos), json!(how)],
+            )
+            .await?;
+
+        result
+            .as_u64()
+            .ok_or_else(|| AppError::Parse("Invalid position returned".to_string()))
+    }
+
+    /// 等待队列中全部任务的 GID，按队列顺序排列
+    pub async fn get_waiting_order(&self) -> Result<Vec<String>, AppError> {
+        let tasks = self
+            .fetch_all_tasks(TaskSource::Waiting, &json!(["gid", "status"]))
+            .await?;
+        Ok(tasks.into_iter().map(|task| task.gid).collect())
+    }
+
+    /// 获取单个任务的选项
+    pub async fn get_option(&self, gid: &str) -> Result<HashMap<String, String>, AppError> {
+        let result = self
+            .make_rpc_call("aria2.getOption", vec![json!(gid)])
+            .await?;
+
+        let options_map = result
+            .as_object()
+            .ok_or_else(|| AppError::Parse("Expected object as task options result".to_string()))?
+            .iter()
+            .filter_map(|(key, value)| value.as_str().map(|s| (key.clone(), s.to_string())))
+            .collect();
+
+        Ok(options_map)
+    }
+
+    /// 更改单个任务的选项，部分选项会让 aria2 重新开始活动中的任务
+    pub async fn change_option(
+        &self,
+        gid: &str,
+        options: HashMap<String, String>,
+    ) -> Result<(), AppError> {
+        let options_obj = serde_json::Value::Object(
+            options
+                .into_iter()
+                .map(|(k, v)| (k, serde_json::Value::String(v)))
+                .collect(),
+        );
+
+        self.make_rpc_call("aria2.changeOption", vec![json!(gid), options_obj])
+            .await?;
+        Ok(())
+    }
+
     /// 获取全局选项
-    pub async fn get_global_option(&self) -> Result<HashMap<String, String>, String> {
+    pub async fn get_global_option(&self) -> Result<HashMap<String, String>, AppError> {
         let result = self.make_rpc_call("aria2.getGlobalOption", vec![]).await?;
 
         // 将返回的JSON对象转换为HashMap<String, String>
         let options_map = result
             .as_object()
-            .ok_or("Expected object as global options result")?
+            .ok_or_else(|| AppError::Parse("Expected object as global options result".to_string()))?
             .iter()
             .filter_map(|(key, value)| {
                 // 将每个值转换为字符串
@@ -687,7 +1117,7 @@ impl Aria2cClient {
     pub async fn change_global_option(
         &self,
         options: HashMap<String, String>,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         // 将 HashMap 转换为 JSON 对象
         println!("options: {:?}", options);
         let options_obj: serde_json::Value = serde_json::Value::Object(
@@ -0,0 +1,274 @@
+use crate::aria2c::download_manager::DownloadFile;
+use crate::error::AppError;
+use crate::torrent::meta::TorrentMeta;
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 多文件种子的文件选择条件，最终转换为 aria2 的 `select-file` 选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileSelection {
+    /// 选中的文件序号（从 1 开始，与 aria2 的 `index` 一致），不指定时从全部文件开始筛选
+    pub indexes: Option<Vec<usize>>,
+    /// 只保留这些扩展名的文件，例如 ["mkv", "srt"]
+    pub extensions: Option<Vec<String>>,
+    /// 只保留匹配的文件，例如 "*.mkv"、"**/Season 1/*"，文件名或完整路径匹配即可
+    pub patterns: Option<Vec<String>>,
+    /// 跳过大于该字节数的文件
+    pub max_file_size: Option<u64>,
+}
+
+impl FileSelection {
+    /// 根据任务的文件列表计算 `select-file` 的值，例如 "1-3,5"
+    pub fn resolve(&self, files: &[DownloadFile]) -> Result<String, AppError> {
+        let candidates = files
+            .iter()
+            .map(|file| {
+                let index: usize = file.index.parse().map_err(|_| {
+                    AppError::Parse(format!("Invalid file index returned: {}", file.index))
+                })?;
+                Ok((index, file.path.clone(), file.length.parse().unwrap_or(0)))
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        self.select(candidates)
+    }
+
+    /// 根据解析出的种子内容计算 `select-file`，添加任务时即可确定，不需要先添加再修改
+    pub fn resolve_torrent(&self, meta: &TorrentMeta) -> Result<String, AppError> {
+        let candidates = meta
+            .files
+            .iter()
+            .filter(|file| !file.padding)
+            .map(|file| (file.index, file.path.join("/"), file.length))
+            .collect();
+        self.select(candidates)
+    }
+
+    /// 按条件筛选 `(序号, 路径, 大小)`
+    fn select(&self, files: Vec<(usize, String, u64)>) -> Result<String, AppError> {
+        let patterns = self
+            .patterns
+            .iter()
+            .flatten()
+            .map(|pattern| {
+                Pattern::new(pattern).map_err(|e| {
+                    AppError::InvalidInput(format!("Invalid file pattern {}: {}", pattern, e))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let extensions: Vec<String> = self
+            .extensions
+            .iter()
+            .flatten()
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .collect();
+
+        let mut selected = Vec::new();
+        for (index, path, length) in files {
+            if let Some(indexes) = &self.indexes {
+                if !indexes.contains(&index) {
+                    continue;
+                }
+            }
+
+            // 扩展名和通配符任一匹配即可
+            if !extensions.is_empty() || !patterns.is_empty() {
+                let matched =
+                    matches_extension(&path, &extensions) || matches_pattern(&path, &patterns);
+                if !matched {
+                    continue;
+                }
+            }
+
+            if let Some(max_size) = self.max_file_size {
+                if length > max_size {
+                    continue;
+                }
+            }
+
+            selected.push(index);
+        }
+
+        if selected.is_empty() {
+            return Err(AppError::InvalidInput(
+                "No files match the selection".to_string(),
+            ));
+        }
+
+        Ok(format_select_file(selected))
+    }
+}
+
+fn matches_extension(path: &str, extensions: &[String]) -> bool {
+    Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| extensions.contains(&ext))
+}
+
+fn matches_pattern(path: &str, patterns: &[Pattern]) -> bool {
+    let options = MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: false,
+        require_literal_leading_dot: false,
+    };
+    let normalized = path.replace('\\', "/");
+    let file_name = Path::new(&normalized)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    patterns.iter().any(|pattern| {
+        pattern.matches_with(&file_name, options) || pattern.matches_with(&normalized, options)
+    })
+}
+
+/// 把序号列表压缩为 aria2 的区间格式，例如 [1, 2, 3, 5] -> "1-3,5"
+fn format_select_file(mut indexes: Vec<usize>) -> String {
+    indexes.sort_unstable();
+    indexes.dedup();
+
+    let mut ranges: Vec<String> = Vec::new();
+    let mut iter = indexes.into_iter().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap_or(end);
+        }
+        if start == end {
+            ranges.push(start.to_string());
+        } else {
+            ranges.push(format!("{}-{}", start, end));
+        }
+    }
+    ranges.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::torrent::meta::TorrentFile;
+
+    fn download_file(index: usize, path: &str, length: u64) -> DownloadFile {
+        DownloadFile {
+            index: index.to_string(),
+            path: path.to_string(),
+            length: length.to_string(),
+            completed_length: "0".to_string(),
+            selected: "true".to_string(),
+            uris: Vec::new(),
+        }
+    }
+
+    fn files() -> Vec<DownloadFile> {
+        vec![
+            download_file(1, "/dl/Show/Season 1/e01.MKV", 700),
+            download_file(2, "/dl/Show/Season 1/e01.srt", 10),
+            download_file(3, "/dl/Show/Season 2/e01.mkv", 900),
+            download_file(4, "/dl/Show/readme.txt", 1),
+            download_file(5, "C:\\dl\\Show\\extras\\sample.mkv", 50),
+        ]
+    }
+
+    #[test]
+    fn compresses_index_ranges() {
+        assert_eq!(format_select_file(vec![5, 1, 2, 3, 3, 7, 8]), "1-3,5,7-8");
+        assert_eq!(format_select_file(vec![4]), "4");
+        assert_eq!(format_select_file(vec![]), "");
+    }
+
+    #[test]
+    fn matches_extensions_case_insensitively() {
+        let selection = FileSelection {
+            extensions: Some(vec![".mkv".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(selection.resolve(&files()).unwrap(), "1,3,5");
+    }
+
+    #[test]
+    fn matches_globs_against_names_and_paths() {
+        let by_name = FileSelection {
+            patterns: Some(vec!["e01.*".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(by_name.resolve(&files()).unwrap(), "1-3");
+
+        // 反斜杠路径同样按目录匹配
+        let by_dir = FileSelection {
+            patterns: Some(vec!["**/season 1/*".to_string(), "**/extras/*".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(by_dir.resolve(&files()).unwrap(), "1-2,5");
+    }
+
+    #[test]
+    fn combines_indexes_filters_and_size_limits() {
+        let selection = FileSelection {
+            indexes: Some(vec![1, 2, 3, 4]),
+            extensions: Some(vec!["srt".to_string()]),
+            patterns: Some(vec!["*.mkv".to_string()]),
+            max_file_size: Some(800),
+        };
+        assert_eq!(selection.resolve(&files()).unwrap(), "1-2");
+    }
+
+    #[test]
+    fn reports_empty_selections_and_bad_patterns() {
+        let none = FileSelection {
+            extensions: Some(vec!["iso".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            none.resolve(&files()).unwrap_err().message(),
+            "No files match the selection"
+        );
+
+        let bad = FileSelection {
+            patterns: Some(vec!["[".to_string()]),
+            ..Default::default()
+        };
+        assert!(bad
+            .resolve(&files())
+            .unwrap_err()
+            .message()
+            .starts_with("Invalid file pattern ["));
+    }
+
+    #[test]
+    fn resolves_from_torrent_contents_without_padding() {
+        let file = |index: usize, path: &[&str], length: u64, padding: bool| TorrentFile {
+            index,
+            path: path.iter().map(|part| part.to_string()).collect(),
+            length,
+            padding,
+        };
+        let meta = TorrentMeta {
+            name: "Show".to_string(),
+            info_hash_v1: None,
+            info_hash_v2: None,
+            piece_length: 16384,
+            total_length: 0,
+            files: vec![
+                file(1, &["Show", "a.mkv"], 100, false),
+                file(2, &["Show", ".pad", "100"], 100, true),
+                file(3, &["Show", "Subs", "a.srt"], 1, false),
+            ],
+            file_tree: Vec::new(),
+            trackers: Vec::new(),
+            web_seeds: Vec::new(),
+            comment: None,
+            created_by: None,
+            creation_date: None,
+            private: false,
+        };
+        let all = FileSelection::default();
+        assert_eq!(all.resolve_torrent(&meta).unwrap(), "1,3");
+
+        let subs = FileSelection {
+            patterns: Some(vec!["Show/Subs/*".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(subs.resolve_torrent(&meta).unwrap(), "3");
+    }
+}
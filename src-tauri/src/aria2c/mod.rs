@@ -1,9 +1,17 @@
 pub mod aria2c;
-pub mod command_shell;
 pub mod download_commands;
 pub mod download_manager;
+pub mod file_selection;
+pub mod notifications;
+pub mod process_log;
+pub mod restart;
 
-pub use aria2c::{get_aria2c_info, start_aria2c, stop_aria2c, Aria2cState};
-pub use command_shell::tell_torrent_info;
+pub use aria2c::{
+    connect_external_daemon, disconnect_external_daemon, endpoint_from_settings, get_aria2c_info,
+    start_aria2c, stop_aria2c, Aria2cState,
+};
 pub use download_commands::*;
+pub use notifications::{
+    start_notification_listener, Aria2cEvents, DownloadEvent, DownloadEventKind,
+};
 // 注意：我们只导出需要的项，避免未使用的导入警告
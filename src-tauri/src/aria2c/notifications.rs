@@ -0,0 +1,241 @@
+use crate::aria2c::aria2c::Aria2cState;
+use crate::aria2c::download_commands::reapply_task_settings;
+use crate::config::settings::DownloadSettings;
+use crate::schedule::scheduler::Scheduler;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+use tokio::sync::{broadcast, watch};
+use tokio_tungstenite::tungstenite::Message;
+
+/// 重连等待时间的上下限 (毫秒)
+const RECONNECT_MIN_DELAY_MS: u64 = 500;
+const RECONNECT_MAX_DELAY_MS: u64 = 10_000;
+
+/// aria2c 推送的下载通知类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DownloadEventKind {
+    Start,
+    Pause,
+    Stop,
+    Complete,
+    Error,
+    BtComplete,
+}
+
+impl DownloadEventKind {
+    /// 将 aria2 的通知方法名映射为事件类型
+    pub fn from_method(method: &str) -> Option<Self> {
+        match method {
+            "aria2.onDownloadStart" => Some(Self::Start),
+            "aria2.onDownloadPause" => Some(Self::Pause),
+            "aria2.onDownloadStop" => Some(Self::Stop),
+            "aria2.onDownloadComplete" => Some(Self::Complete),
+            "aria2.onDownloadError" => Some(Self::Error),
+            "aria2.onBtDownloadComplete" => Some(Self::BtComplete),
+            _ => None,
+        }
+    }
+
+    /// 发送给前端的 Tauri 事件名
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::Start => "download-start",
+            Self::Pause => "download-pause",
+            Self::Stop => "download-stop",
+            Self::Complete => "download-complete",
+            Self::Error => "download-error",
+            Self::BtComplete => "download-bt-complete",
+        }
+    }
+}
+
+/// 单条下载通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadEvent {
+    pub gid: String,
+    pub kind: DownloadEventKind,
+}
+
+/// WebSocket 连接状态，通过 `aria2c-connection` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+struct ConnectionStatus {
+    connected: bool,
+}
+
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// 下载通知的分发中心：转发给前端，同时广播给 Rust 侧的订阅者（历史记录等）
+#[derive(Clone)]
+pub struct Aria2cEvents {
+    sender: broadcast::Sender<DownloadEvent>,
+}
+
+impl Aria2cEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DownloadEvent> {
+        self.sender.subscribe()
+    }
+
+    /// 按类型发送独立事件和汇总的 `download-event`，再广播给 Rust 侧
+    fn dispatch<R: tauri::Runtime>(&self, app_handle: &tauri::AppHandle<R>, event: DownloadEvent) {
+        if let Err(e) = app_handle.emit(event.kind.event_name(), &event) {
+            eprintln!("Failed to emit {}: {}", event.kind.event_name(), e);
+        }
+        if let Err(e) = app_handle.emit("download-event", &event) {
+            eprintln!("Failed to emit download-event: {}", e);
+        }
+        // 没有订阅者时 send 会返回错误，可以忽略
+        let _ = self.sender.send(event);
+    }
+}
+
+/// 解析 aria2 推送的 JSON-RPC 通知，例如：
+/// `{"jsonrpc":"2.0","method":"aria2.onDownloadStart","params":[{"gid":"2089b05ecca3d829"}]}`
+fn parse_notification(text: &str) -> Vec<DownloadEvent> {
+    let value: serde_json::Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => {
+            println!("忽略无法解析的 aria2c 消息: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let Some(kind) = value
+        .get("method")
+        .and_then(|v| v.as_str())
+        .and_then(DownloadEventKind::from_method)
+    else {
+        return Vec::new();
+    };
+
+    value
+        .get("params")
+        .and_then(|v| v.as_array())
+        .map(|params| {
+            params
+                .iter()
+                .filter_map(|param| param.get("gid")?.as_str())
+                .map(|gid| DownloadEvent {
+                    gid: gid.to_string(),
+                    kind,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 读取已建立连接上的通知，直到连接断开或连接参数变化
+async fn read_notifications<R: tauri::Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    events: &Aria2cEvents,
+    endpoint_changed: &mut watch::Receiver<()>,
+    mut stream: WsStream,
+) -> Result<(), String> {
+    loop {
+        let message = tokio::select! {
+            message = stream.next() => message,
+            // 切换到其他守护进程后关闭旧连接，重新连接新地址
+            _ = endpoint_changed.changed() => {
+                let _ = stream.close(None).await;
+                return Err("aria2c endpoint changed".to_string());
+            }
+        };
+        let Some(message) = message else {
+            break;
+        };
+        match message.map_err(|e| format!("WebSocket read failed: {}", e))? {
+            Message::Text(text) => {
+                for event in parse_notification(&text) {
+                    events.dispatch(app_handle, event);
+                }
+            }
+            Message::Close(_) => break,
+            // Ping/Pong 由 tungstenite 自动处理
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// 启动通知监听任务，连接断开后（例如 aria2c 重启）自动重连
+pub fn start_notification_listener<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    aria2c_state: Aria2cState,
+    events: Aria2cEvents,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut delay_ms = RECONNECT_MIN_DELAY_MS;
+        let mut endpoint_changed = aria2c_state.watch_endpoint();
+        loop {
+            // 每次重连都重新读取地址，aria2c 重启后端口可能变化
+            endpoint_changed.borrow_and_update();
+            let ws_url = match aria2c_state.endpoint() {
+                Ok(endpoint) => endpoint.ws_url(),
+                Err(e) => {
+                    eprintln!("Failed to read aria2c endpoint: {}", e);
+                    return;
+                }
+            };
+
+            match tokio_tungstenite::connect_async(ws_url.as_str()).await {
+                Ok((stream, _)) => {
+                    println!("Connected to aria2c notifications at {}", ws_url);
+                    delay_ms = RECONNECT_MIN_DELAY_MS;
+                    let _ =
+                        app_handle.emit("aria2c-connection", ConnectionStatus { connected: true });
+
+                    // aria2c 启动或重启后重新应用按任务保存的选项
+                    let settings_state = app_handle
+                        .state::<Arc<Mutex<DownloadSettings>>>()
+                        .inner()
+                        .clone();
+                    let state = aria2c_state.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = reapply_task_settings(&state, &settings_state).await {
+                            eprintln!("Failed to re-apply task settings: {}", e);
+                        }
+                    });
+
+                    // 重新发送当前时间段的限速
+                    if let Some(scheduler) = app_handle.try_state::<Scheduler>() {
+                        scheduler.wake();
+                    }
+
+                    match read_notifications(&app_handle, &events, &mut endpoint_changed, stream)
+                        .await
+                    {
+                        Ok(()) => println!("aria2c notification connection closed"),
+                        Err(e) => println!("aria2c notification connection lost: {}", e),
+                    }
+                    let _ =
+                        app_handle.emit("aria2c-connection", ConnectionStatus { connected: false });
+                }
+                Err(e) => {
+                    // aria2c 尚未启动或正在重启，按指数退避重试
+                    println!("aria2c notification connect failed: {}", e);
+                }
+            }
+
+            // 连接参数变化时立即重连
+            if endpoint_changed.has_changed().unwrap_or(false) {
+                delay_ms = RECONNECT_MIN_DELAY_MS;
+                continue;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)) => {
+                    delay_ms = (delay_ms * 2).min(RECONNECT_MAX_DELAY_MS);
+                }
+                _ = endpoint_changed.changed() => delay_ms = RECONNECT_MIN_DELAY_MS,
+            }
+        }
+    });
+}
@@ -0,0 +1,88 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// 单个日志文件的最大字节数，超过后滚动
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+/// 保留的历史日志数量 (aria2c.log.1 ~ aria2c.log.N)
+const MAX_LOG_FILES: u32 = 3;
+
+/// aria2c 输出的滚动日志
+pub struct ProcessLog {
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+impl ProcessLog {
+    /// 打开（或创建）日志文件，失败时只打印警告，不影响 aria2c 运行
+    pub fn open(path: PathBuf) -> Self {
+        if let Some(dir) = path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                eprintln!("Failed to create log directory {}: {}", dir.display(), e);
+            }
+        }
+
+        let (file, size) = match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => {
+                let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+                (Some(file), size)
+            }
+            Err(e) => {
+                eprintln!("Failed to open aria2c log {}: {}", path.display(), e);
+                (None, 0)
+            }
+        };
+
+        Self { path, file, size }
+    }
+
+    /// 写入一行日志，`source` 标记来源 (stdout/stderr/supervisor)
+    pub fn write_line(&mut self, source: &str, line: &[u8]) {
+        if self.size >= MAX_LOG_SIZE {
+            self.rotate();
+        }
+
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+
+        let text = String::from_utf8_lossy(line);
+        let entry = format!(
+            "{} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            source,
+            text.trim_end()
+        );
+
+        if file.write_all(entry.as_bytes()).is_ok() {
+            self.size += entry.len() as u64;
+        }
+    }
+
+    /// 将 aria2c.log 依次重命名为 aria2c.log.1、aria2c.log.2 …，最旧的被丢弃
+    fn rotate(&mut self) {
+        self.file = None;
+
+        for index in (1..MAX_LOG_FILES).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                let _ = fs::rename(&from, self.rotated_path(index + 1));
+            }
+        }
+        let _ = fs::rename(&self.path, self.rotated_path(1));
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .ok();
+        self.size = 0;
+    }
+
+    fn rotated_path(&self, index: u32) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::aria2c::download_manager::Aria2cClient;
+use crate::error::AppError;
+use crate::torrent::cache::TorrentCache;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 等待原任务暂停或进入已停止列表的最长时间
+const STATUS_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 重新添加任务的来源
+enum RestartSource {
+    /// 缓存的种子文件
+    Torrent(Vec<u8>),
+    /// 普通下载的镜像地址，或由 info-hash 生成的磁力链接
+    Uris(Vec<String>),
+}
+
+/// 用原任务的来源和全部选项添加新任务，新任务被 aria2 接受后才清除原任务，返回新的 GID
+///
+/// 新任务先以暂停状态添加；aria2 因 info-hash 或文件重复拒绝时暂停原任务后重试，
+/// 仍然失败则恢复原任务。原任务移除后再开始新任务，两者不会同时下载同一个文件；
+/// 原任务处于暂停状态时新任务同样保持暂停
+pub async fn restart_task(
+    client: &Aria2cClient,
+    torrent_cache: &TorrentCache,
+    gid: &str,
+) -> Result<String, AppError> {
+    let keys = ["status", "infoHash", "files", "bittorrent"]
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    let status = client.tell_status(gid, Some(keys)).await?;
+    let source = restart_source(&status, torrent_cache)?;
+    let mut options = client.get_option(gid).await?;
+    let task_status = status.get("status").and_then(|v| v.as_str()).unwrap_or("");
+    let queued = matches!(task_status, "active" | "waiting" | "paused");
+    // 原任务在队列中时保持原来的运行状态；已停止的任务按原任务的 pause 选项决定
+    let start_new = match task_status {
+        "active" | "waiting" => true,
+        "paused" => false,
+        _ => options.get("pause").map(String::as_str) != Some("true"),
+    };
+    // 覆盖原任务的 pause 选项：新任务先暂停添加，移除原任务后再开始
+    options.insert("pause".to_string(), "true".to_string());
+
+    let new_gid = match add_source(client, &source, &options).await {
+        Ok(new_gid) => new_gid,
+        Err(e) if queued => {
+            println!(
+                "Replacement of task {} was rejected ({}), pausing the original and retrying",
+                gid, e
+            );
+            let resume_original = task_status != "paused";
+            if resume_original {
+                client.pause_download(gid).await?;
+                wait_for_status(client, gid, &["paused"]).await?;
+            }
+            match add_source(client, &source, &options).await {
+                Ok(new_gid) => new_gid,
+                Err(e) => {
+                    if resume_original {
+                        if let Err(e) = client.unpause_download(gid).await {
+                            eprintln!("Failed to resume task {} after restart failed: {}", gid, e);
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => return Err(e),
+    };
+
+    // 新任务已被接受，移除原任务后再开始新任务
+    if queued {
+        client.force_remove(gid).await?;
+        wait_for_status(client, gid, &["removed", "complete", "error"]).await?;
+    }
+    if let Err(e) = client.remove_download_result(gid).await {
+        eprintln!("Failed to remove result of restarted task {}: {}", gid, e);
+    }
+    if start_new {
+        client.unpause_download(&new_gid).await?;
+    }
+
+    Ok(new_gid)
+}
+
+async fn add_source(
+    client: &Aria2cClient,
+    source: &RestartSource,
+    options: &HashMap<String, String>,
+) -> Result<String, AppError> {
+    match source {
+        RestartSource::Torrent(data) => {
+            client
+                .add_torrent(data.clone(), None, Some(options.clone()))
+                .await
+        }
+        RestartSource::Uris(uris) => client.add_uri(uris.clone(), Some(options.clone())).await,
+    }
+}
+
+fn restart_source(status: &Value, torrent_cache: &TorrentCache) -> Result<RestartSource, AppError> {
+    if let Some(info_hash) = status.get("infoHash").and_then(|v| v.as_str()) {
+        if let Some(data) = torrent_cache.load(info_hash) {
+            return Ok(RestartSource::Torrent(data));
+        }
+        return Ok(RestartSource::Uris(vec![magnet_from_status(
+            info_hash, status,
+        )]));
+    }
+
+    // HTTP/FTP 以及 Metalink 任务：同一个文件的全部镜像地址
+    let mut uris: Vec<String> = Vec::new();
+    let file_uris = status.pointer("/files/0/uris").and_then(|v| v.as_array());
+    for uri in file_uris.into_iter().flatten() {
+        if let Some(uri) = uri.get("uri").and_then(|v| v.as_str()) {
+            if !uris.iter().any(|u| u == uri) {
+                uris.push(uri.to_string());
+            }
+        }
+    }
+    if uris.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Task cannot be restarted: missing source information".to_string(),
+        ));
+    }
+    Ok(RestartSource::Uris(uris))
+}
+
+/// 没有缓存的种子时，用 info-hash、名称和 Tracker 生成磁力链接
+fn magnet_from_status(info_hash: &str, status: &Value) -> String {
+    let encode =
+        |value: &str| url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>();
+
+    let mut magnet = format!("magnet:?xt=urn:btih:{}", info_hash);
+    if let Some(name) = status
+        .pointer("/bittorrent/info/name")
+        .and_then(|v| v.as_str())
+    {
+        magnet.push_str(&format!("&dn={}", encode(name)));
+    }
+
+    let tiers = status
+        .pointer("/bittorrent/announceList")
+        .and_then(|v| v.as_array());
+    for tracker in tiers
+        .into_iter()
+        .flatten()
+        .filter_map(|tier| tier.as_array())
+        .flatten()
+        .filter_map(|tracker| tracker.as_str())
+    {
+        magnet.push_str(&format!("&tr={}", encode(tracker)));
+    }
+    magnet
+}
+
+/// forcePause 和 forceRemove 是异步完成的，等待任务进入指定状态
+async fn wait_for_status(
+    client: &Aria2cClient,
+    gid: &str,
+    expected: &[&str],
+) -> Result<(), AppError> {
+    let deadline = tokio::time::Instant::now() + STATUS_WAIT_TIMEOUT;
+    loop {
+        let status = client
+            .tell_status(gid, Some(vec!["status".to_string()]))
+            .await?;
+        if status
+            .get("status")
+            .and_then(|v| v.as_str())
+            .is_some_and(|status| expected.contains(&status))
+        {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(AppError::Process(format!(
+                "Timed out waiting for task {} to become {}",
+                gid,
+                expected.join("/")
+            )));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
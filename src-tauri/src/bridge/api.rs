@@ -0,0 +1,265 @@
+use crate::aria2c::aria2c::Aria2cState;
+use crate::aria2c::download_manager::{TaskFilter, TaskKind};
+use crate::bridge::events::EventHub;
+use crate::bridge::server::read_json;
+use crate::config::category::TaskDescriptor;
+use crate::config::settings::{DownloadSettings, NewTaskSettings};
+use crate::error::AppError;
+use crate::torrent::cache::TorrentCache;
+use crate::torrent::magnet::MagnetLink;
+use base64::Engine;
+use rouille::{Request, Response};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+
+/// 列表接口默认和最大的每页数量
+const DEFAULT_PAGE_SIZE: u64 = 100;
+const MAX_PAGE_SIZE: u64 = 1000;
+
+/// aria2 的任务状态
+const TASK_STATUSES: [&str; 6] = [
+    "active", "waiting", "paused", "complete", "error", "removed",
+];
+
+/// `POST /downloads` 的请求体
+#[derive(Debug, Deserialize)]
+struct NewDownload {
+    #[serde(flatten)]
+    source: DownloadSource,
+    /// 下载目录、限速、分类等，与 `add_download_*` 命令的 `task_settings` 相同
+    #[serde(default)]
+    task_settings: Option<NewTaskSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum DownloadSource {
+    Url {
+        urls: Vec<String>,
+    },
+    Magnet {
+        magnet: String,
+    },
+    /// Base64 编码的种子内容
+    Torrent {
+        torrent_base64: String,
+    },
+}
+
+/// 本地 HTTP 接口的下载管理 API，直接调用 aria2c，不经过前端
+#[derive(Clone)]
+pub struct BridgeApi {
+    aria2c_state: Aria2cState,
+    settings_state: Arc<Mutex<DownloadSettings>>,
+    torrent_cache: TorrentCache,
+    events: EventHub,
+}
+
+impl BridgeApi {
+    pub fn new(
+        aria2c_state: Aria2cState,
+        settings_state: Arc<Mutex<DownloadSettings>>,
+        torrent_cache: TorrentCache,
+        events: EventHub,
+    ) -> Self {
+        Self {
+            aria2c_state,
+            settings_state,
+            torrent_cache,
+            events,
+        }
+    }
+
+    /// 处理 `/downloads`、`/stats` 和 `/events` 下的请求，路径不属于 API 时返回 `None`
+    pub fn handle(&self, request: &Request) -> Option<Response> {
+        let url = request.url();
+        let segments: Vec<&str> = url.trim_matches('/').split('/').collect();
+
+        let result = match (request.method(), segments.as_slice()) {
+            ("POST", ["downloads"]) => match read_json(request) {
+                Ok(body) => self.add_download(body),
+                Err(response) => return Some(response),
+            },
+            ("GET", ["downloads"]) => self.list_downloads(request),
+            ("GET", ["downloads", gid]) => self.get_download(gid),
+            ("DELETE", ["downloads", gid]) => self.remove_download(gid),
+            ("POST", ["downloads", gid, "pause"]) => self.pause_download(gid),
+            ("POST", ["downloads", gid, "resume"]) => self.resume_download(gid),
+            ("GET", ["stats"]) => self.get_stats(),
+            ("GET", ["events"]) => Ok(self.open_event_stream(request)),
+            _ => return None,
+        };
+
+        Some(result.unwrap_or_else(error_response))
+    }
+
+    fn add_download(&self, body: serde_json::Value) -> Result<Response, AppError> {
+        let download: NewDownload = serde_json::from_value(body)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid download request: {}", e)))?;
+        let task_settings = download.task_settings.as_ref();
+        let client = self.aria2c_state.client()?;
+
+        let gid = match download.source {
+            DownloadSource::Url { urls } => {
+                if urls.is_empty() {
+                    return Err(AppError::InvalidInput("No valid URLs provided".to_string()));
+                }
+                let options = self
+                    .settings_state
+                    .lock()?
+                    .to_aria2c_options(task_settings, &TaskDescriptor::from_uris(&urls))?;
+                block_on(client.add_uri(urls, Some(options)))?
+            }
+            DownloadSource::Magnet { magnet } => {
+                let link = MagnetLink::parse(&magnet)?;
+                let options = self
+                    .settings_state
+                    .lock()?
+                    .to_aria2c_options(task_settings, &TaskDescriptor::from_magnet(&link))?;
+                block_on(client.add_uri(vec![magnet], Some(options)))?
+            }
+            DownloadSource::Torrent { torrent_base64 } => {
+                let torrent_data =
+                    base64::engine::general_purpose::STANDARD.decode(&torrent_base64)?;
+                let meta = self.torrent_cache.store(&torrent_data)?;
+                let options = self
+                    .settings_state
+                    .lock()?
+                    .to_aria2c_options(task_settings, &TaskDescriptor::from_torrent(&meta))?;
+                block_on(client.add_torrent(torrent_data, None, Some(options)))?
+            }
+        };
+
+        Ok(Response::json(&serde_json::json!({ "gid": gid })).with_status_code(201))
+    }
+
+    /// 支持 `status`、`name`、`kind`、`offset`、`limit` 查询参数
+    fn list_downloads(&self, request: &Request) -> Result<Response, AppError> {
+        let status = request.get_param("status");
+        if let Some(status) = &status {
+            if !TASK_STATUSES.contains(&status.as_str()) {
+                return Err(AppError::InvalidInput(format!(
+                    "Unknown status: {}",
+                    status
+                )));
+            }
+        }
+        let kind = match request.get_param("kind").as_deref() {
+            None => None,
+            Some("torrent") => Some(TaskKind::Torrent),
+            Some("http") => Some(TaskKind::Http),
+            Some(kind) => {
+                return Err(AppError::InvalidInput(format!("Unknown kind: {}", kind)));
+            }
+        };
+        let filter = TaskFilter {
+            status,
+            name: request.get_param("name"),
+            dir: None,
+            kind,
+        };
+        let offset = parse_param(request, "offset")?.unwrap_or(0);
+        let limit = parse_param(request, "limit")?
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .min(MAX_PAGE_SIZE);
+
+        let client = self.aria2c_state.client()?;
+        let page = block_on(client.list_all_tasks(offset, limit, Some(&filter)))?;
+        Ok(Response::json(&page))
+    }
+
+    fn get_download(&self, gid: &str) -> Result<Response, AppError> {
+        let gid = validate_gid(gid)?;
+        let client = self.aria2c_state.client()?;
+        let task = block_on(client.get_download_status(gid))?;
+        Ok(Response::json(&task))
+    }
+
+    fn remove_download(&self, gid: &str) -> Result<Response, AppError> {
+        let gid = validate_gid(gid)?;
+        let client = self.aria2c_state.client()?;
+        block_on(client.remove_download(gid))?;
+
+        // 任务已删除，不再需要保存它的选项
+        let mut settings = self.settings_state.lock()?;
+        if settings.task_settings.remove(gid).is_some() {
+            settings.save()?;
+        }
+
+        Ok(Response::json(&serde_json::json!({ "gid": gid })))
+    }
+
+    fn pause_download(&self, gid: &str) -> Result<Response, AppError> {
+        let gid = validate_gid(gid)?;
+        let client = self.aria2c_state.client()?;
+        block_on(client.pause_download(gid))?;
+        Ok(Response::json(&serde_json::json!({ "gid": gid })))
+    }
+
+    fn resume_download(&self, gid: &str) -> Result<Response, AppError> {
+        let gid = validate_gid(gid)?;
+        let client = self.aria2c_state.client()?;
+        block_on(client.unpause_download(gid))?;
+        Ok(Response::json(&serde_json::json!({ "gid": gid })))
+    }
+
+    fn get_stats(&self) -> Result<Response, AppError> {
+        let client = self.aria2c_state.client()?;
+        let stat = block_on(client.get_global_stat())?;
+        Ok(Response::json(&stat))
+    }
+
+    /// 任务事件和全局速度统计的 SSE 事件流，断线重连时按 `Last-Event-ID` 补发
+    fn open_event_stream(&self, request: &Request) -> Response {
+        match self.events.open_stream(request.header("Last-Event-ID")) {
+            Some(stream) => stream.into_response(),
+            None => Response::json(&serde_json::json!({
+                "status": "error",
+                "message": "Too many event streams"
+            }))
+            .with_status_code(503),
+        }
+    }
+}
+
+/// rouille 的工作线程不在异步运行时中，直接阻塞等待 RPC 结果
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tauri::async_runtime::block_on(future)
+}
+
+/// aria2 的 GID 为 16 位十六进制
+fn validate_gid(gid: &str) -> Result<&str, AppError> {
+    if gid.len() == 16 && gid.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(gid)
+    } else {
+        Err(AppError::InvalidInput(format!("Invalid GID: {}", gid)))
+    }
+}
+
+fn parse_param(request: &Request, name: &str) -> Result<Option<u64>, AppError> {
+    request
+        .get_param(name)
+        .map(|value| {
+            value
+                .parse::<u64>()
+                .map_err(|_| AppError::InvalidInput(format!("Invalid {}: {}", name, value)))
+        })
+        .transpose()
+}
+
+/// 错误响应：参数错误 400，任务不存在 404，aria2c 不可用 503，其他 500
+fn error_response(error: AppError) -> Response {
+    let status_code = match &error {
+        AppError::InvalidInput(_) => 400,
+        AppError::Rpc { message, .. } if message.contains("not found") => 404,
+        AppError::Rpc { .. } => 400,
+        AppError::Transport(_) => 503,
+        _ => 500,
+    };
+    Response::json(&serde_json::json!({
+        "status": "error",
+        "kind": error.kind(),
+        "message": error.message()
+    }))
+    .with_status_code(status_code)
+}
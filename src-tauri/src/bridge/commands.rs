@@ -0,0 +1,59 @@
+use crate::bridge::config::{normalize_origin, BridgeSettings};
+use crate::bridge::server::BRIDGE_ADDR;
+use crate::bridge::token::BridgeToken;
+use crate::config::settings::DownloadSettings;
+use crate::error::AppError;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+/// 本地 HTTP 接口的连接信息，在设置页中展示给用户填写到浏览器扩展
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeInfo {
+    pub url: String,
+    pub token: String,
+    pub token_path: String,
+    pub allowed_origins: Vec<String>,
+}
+
+/// 获取本地 HTTP 接口的地址、令牌和允许的来源
+#[tauri::command]
+pub async fn get_bridge_info(
+    bridge_token: tauri::State<'_, Arc<BridgeToken>>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<BridgeInfo, AppError> {
+    Ok(BridgeInfo {
+        url: format!("http://{}", BRIDGE_ADDR),
+        token: bridge_token.get()?,
+        token_path: bridge_token.path().to_string_lossy().to_string(),
+        allowed_origins: settings_state.lock()?.bridge.allowed_origins.clone(),
+    })
+}
+
+/// 重新生成令牌，已配置的扩展需要填写新令牌
+#[tauri::command]
+pub async fn regenerate_bridge_token(
+    bridge_token: tauri::State<'_, Arc<BridgeToken>>,
+) -> Result<String, AppError> {
+    bridge_token.regenerate()
+}
+
+/// 更新允许访问本地 HTTP 接口的浏览器来源
+#[tauri::command]
+pub async fn update_bridge_origins(
+    origins: Vec<String>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<BridgeSettings, AppError> {
+    let mut allowed_origins = Vec::new();
+    for origin in origins {
+        let origin = normalize_origin(&origin).map_err(AppError::InvalidInput)?;
+        if !allowed_origins.contains(&origin) {
+            allowed_origins.push(origin);
+        }
+    }
+
+    let mut settings = settings_state.lock()?;
+    settings.bridge.allowed_origins = allowed_origins;
+    settings.save()?;
+
+    Ok(settings.bridge.clone())
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// 本地 HTTP 接口的设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BridgeSettings {
+    /// 允许访问的浏览器来源，例如 "chrome-extension://<扩展ID>"、"moz-extension://<UUID>"
+    ///
+    /// 带 `Origin` 头的请求（来自浏览器）必须在列表中，普通网页无法调用本地接口
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+impl BridgeSettings {
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        normalize_origin(origin).is_ok_and(|origin| self.allowed_origins.contains(&origin))
+    }
+}
+
+/// 规范化来源：去掉首尾空白和末尾的 `/`，协议部分转为小写
+pub fn normalize_origin(origin: &str) -> Result<String, String> {
+    let origin = origin.trim().trim_end_matches('/');
+    let Some((scheme, rest)) = origin.split_once("://") else {
+        return Err(format!("Invalid origin: {}", origin));
+    };
+    if scheme.is_empty()
+        || rest.is_empty()
+        || rest.contains('/')
+        || origin.chars().any(|c| c.is_whitespace())
+    {
+        return Err(format!("Invalid origin: {}", origin));
+    }
+    Ok(format!("{}://{}", scheme.to_ascii_lowercase(), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_origins() {
+        assert_eq!(
+            normalize_origin(" Chrome-Extension://abcdef/ ").unwrap(),
+            "chrome-extension://abcdef"
+        );
+        assert_eq!(
+            normalize_origin("moz-extension://1234-5678").unwrap(),
+            "moz-extension://1234-5678"
+        );
+        for origin in [
+            "abcdef",
+            "://abcdef",
+            "chrome-extension://",
+            "https://example.com/path",
+            "https://exa mple.com",
+        ] {
+            assert!(normalize_origin(origin).is_err(), "{}", origin);
+        }
+    }
+
+    #[test]
+    fn allows_only_listed_origins() {
+        let settings = BridgeSettings {
+            allowed_origins: vec!["chrome-extension://abcdef".to_string()],
+        };
+        assert!(settings.allows_origin("chrome-extension://abcdef"));
+        assert!(settings.allows_origin("CHROME-EXTENSION://abcdef/"));
+        // 扩展 ID 区分大小写
+        assert!(!settings.allows_origin("chrome-extension://ABCDEF"));
+        assert!(!settings.allows_origin("chrome-extension://abcdefg"));
+        assert!(!settings.allows_origin("https://abcdef"));
+        assert!(!settings.allows_origin("null"));
+    }
+}
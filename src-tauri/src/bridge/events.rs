@@ -0,0 +1,428 @@
+use crate::aria2c::{Aria2cEvents, Aria2cState};
+use rouille::{Response, ResponseBody, Upgrade};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+/// 保留的任务事件数量，客户端断线重连时从这里补发
+const REPLAY_CAPACITY: usize = 1000;
+
+/// 同时打开的事件流上限，每个事件流占用一个线程
+pub const MAX_STREAMS: usize = 8;
+
+/// 全局速度统计的推送间隔
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 没有事件时发送注释行的间隔，防止代理或客户端判定连接超时
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 客户端断线后的重连间隔 (毫秒)
+const RETRY_MS: u64 = 3000;
+
+/// 带编号的 SSE 事件
+#[derive(Debug, Clone)]
+struct SseEvent {
+    id: u64,
+    name: &'static str,
+    data: String,
+}
+
+impl SseEvent {
+    fn encode(&self, epoch: i64) -> String {
+        format!(
+            "id: {}-{}\nevent: {}\ndata: {}\n\n",
+            epoch, self.id, self.name, self.data
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct HubState {
+    /// 本次运行的标识（启动时间），事件编号为 `<epoch>-<序号>`，重启后旧编号不会被误用
+    epoch: i64,
+    /// 最近一个事件的编号，任务事件和速度统计共用
+    last_id: u64,
+    /// 最近的任务事件，速度统计只推送给当前连接，不保留
+    replay: VecDeque<SseEvent>,
+    /// 已从保留队列中移除的最大编号，更早的编号无法补发
+    evicted_id: u64,
+    /// 最近一次的速度统计
+    latest_stats: Option<SseEvent>,
+    streams: usize,
+}
+
+impl HubState {
+    /// 通知客户端有事件无法补发，客户端应重新同步任务列表
+    fn reset_event(&self) -> String {
+        format!(
+            "id: {}-{}\nevent: reset\ndata: {{}}\n\n",
+            self.epoch, self.last_id
+        )
+    }
+}
+
+/// `GET /events` 的事件中心：收集任务事件和速度统计，分发给所有事件流
+#[derive(Clone)]
+pub struct EventHub {
+    inner: Arc<(Mutex<HubState>, Condvar)>,
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        let state = HubState {
+            epoch: chrono::Utc::now().timestamp(),
+            ..HubState::default()
+        };
+        Self {
+            inner: Arc::new((Mutex::new(state), Condvar::new())),
+        }
+    }
+
+    fn publish(&self, name: &'static str, data: String, replayable: bool) {
+        let (lock, condvar) = &*self.inner;
+        let Ok(mut state) = lock.lock() else {
+            return;
+        };
+        state.last_id += 1;
+        let event = SseEvent {
+            id: state.last_id,
+            name,
+            data,
+        };
+        if replayable {
+            if state.replay.len() == REPLAY_CAPACITY {
+                if let Some(evicted) = state.replay.pop_front() {
+                    state.evicted_id = evicted.id;
+                }
+            }
+            state.replay.push_back(event);
+        } else {
+            state.latest_stats = Some(event);
+        }
+        condvar.notify_all();
+    }
+
+    fn has_streams(&self) -> bool {
+        self.inner.0.lock().is_ok_and(|state| state.streams > 0)
+    }
+
+    /// 打开事件流，超过上限时返回 `None`
+    ///
+    /// 带 `Last-Event-ID` 时补发之后的任务事件；编号来自上一次运行或已不在保留范围内时
+    /// 先发送 `reset`，客户端应重新调用 `GET /downloads` 同步任务列表
+    pub fn open_stream(&self, last_event_id: Option<&str>) -> Option<SseStream> {
+        let (lock, _) = &*self.inner;
+        let mut state = lock.lock().ok()?;
+        if state.streams >= MAX_STREAMS {
+            return None;
+        }
+        state.streams += 1;
+
+        let mut buffer = format!("retry: {}\n\n", RETRY_MS);
+        if let Some(last_event_id) = last_event_id {
+            let resumed = parse_event_id(last_event_id)
+                .filter(|(epoch, id)| *epoch == state.epoch && *id <= state.last_id)
+                .map(|(_, id)| id);
+            match resumed {
+                Some(id) if id >= state.evicted_id => {
+                    for event in state.replay.iter().filter(|e| e.id > id) {
+                        buffer.push_str(&event.encode(state.epoch));
+                    }
+                }
+                _ => buffer.push_str(&state.reset_event()),
+            }
+        }
+
+        Some(SseStream {
+            hub: self.clone(),
+            cursor: state.last_id,
+            initial: buffer,
+        })
+    }
+}
+
+/// 解析 `<epoch>-<序号>` 格式的事件编号
+fn parse_event_id(id: &str) -> Option<(i64, u64)> {
+    let (epoch, id) = id.trim().split_once('-')?;
+    Some((epoch.parse().ok()?, id.parse().ok()?))
+}
+
+/// 单个客户端的事件流，客户端断开后被丢弃
+pub struct SseStream {
+    hub: EventHub,
+    /// 已发送的最后一个事件编号
+    cursor: u64,
+    /// 连接建立后先发送的内容（重连间隔和补发的事件）
+    initial: String,
+}
+
+impl SseStream {
+    /// 生成 `text/event-stream` 响应
+    ///
+    /// rouille 的分块响应体会攒满 8 KiB 才发送，因此通过 `Upgrade` 接管连接，
+    /// 发送响应头后在独立线程中逐条写入并立即刷新。这样 tiny_http 会在 200 响应中附带
+    /// `Connection: upgrade` 和空的 `Upgrade` 头，也不会添加 `Content-Length` 或
+    /// `Transfer-Encoding`：响应体以关闭连接结束，这是 HTTP/1.1 允许的写法，
+    /// 非 101 响应中的 `Upgrade` 头会被客户端忽略（见 `streams_events_over_http` 测试）
+    pub fn into_response(self) -> Response {
+        Response {
+            status_code: 200,
+            headers: vec![
+                ("Content-Type".into(), "text/event-stream".into()),
+                ("Cache-Control".into(), "no-cache".into()),
+            ],
+            data: ResponseBody::empty(),
+            upgrade: Some(Box::new(SseUpgrade(Some(self)))),
+        }
+    }
+
+    fn serve(mut self, mut socket: Box<dyn rouille::ReadWrite + Send>) -> std::io::Result<()> {
+        socket.write_all(std::mem::take(&mut self.initial).as_bytes())?;
+        socket.flush()?;
+        loop {
+            let chunk = self.next_chunk()?;
+            socket.write_all(chunk.as_bytes())?;
+            socket.flush()?;
+        }
+    }
+
+    /// 阻塞等待新事件，超时返回保活注释
+    fn next_chunk(&mut self) -> std::io::Result<String> {
+        let (lock, condvar) = &*self.hub.inner;
+        let state = lock
+            .lock()
+            .map_err(|_| std::io::Error::other("event hub lock poisoned"))?;
+        let (state, _) = condvar
+            .wait_timeout_while(state, KEEP_ALIVE_INTERVAL, |state| {
+                state.last_id <= self.cursor
+            })
+            .map_err(|_| std::io::Error::other("event hub lock poisoned"))?;
+
+        if state.last_id <= self.cursor {
+            return Ok(": keep-alive\n\n".to_string());
+        }
+
+        let mut chunk = String::new();
+        // 客户端读取太慢，未发送的事件已被移出保留队列
+        if self.cursor < state.evicted_id {
+            chunk.push_str(&state.reset_event());
+        }
+        for event in state.replay.iter().filter(|e| e.id > self.cursor) {
+            chunk.push_str(&event.encode(state.epoch));
+        }
+        if let Some(stats) = state.latest_stats.as_ref().filter(|e| e.id > self.cursor) {
+            chunk.push_str(&stats.encode(state.epoch));
+        }
+        self.cursor = state.last_id;
+        Ok(chunk)
+    }
+}
+
+struct SseUpgrade(Option<SseStream>);
+
+impl Upgrade for SseUpgrade {
+    fn build(&mut self, socket: Box<dyn rouille::ReadWrite + Send>) {
+        let Some(stream) = self.0.take() else {
+            return;
+        };
+        // 释放 HTTP 工作线程，写入失败说明客户端已断开
+        std::thread::spawn(move || {
+            let _ = stream.serve(socket);
+        });
+    }
+}
+
+impl Drop for SseStream {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.hub.inner.0.lock() {
+            state.streams = state.streams.saturating_sub(1);
+        }
+    }
+}
+
+/// 订阅下载通知作为任务事件，有客户端连接时定时推送全局速度统计
+pub fn start_event_hub(hub: EventHub, aria2c_state: Aria2cState, events: Aria2cEvents) {
+    let mut receiver = events.subscribe();
+    let download_hub = hub.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => match serde_json::to_string(&event) {
+                    Ok(data) => download_hub.publish(event.kind.event_name(), data, true),
+                    Err(e) => eprintln!("Failed to serialize download event: {}", e),
+                },
+                Err(RecvError::Lagged(skipped)) => {
+                    eprintln!("Bridge event stream lagged, {} events skipped", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(STATS_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !hub.has_streams() {
+                continue;
+            }
+            // aria2c 未运行时跳过，恢复后继续推送
+            let Ok(client) = aria2c_state.client() else {
+                continue;
+            };
+            if let Ok(stat) = client.get_global_stat().await {
+                hub.publish("stats", stat.to_string(), false);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 读取响应直到出现 `needle`，返回读到的全部内容
+    fn read_until(response: &mut impl std::io::Read, needle: &str) -> String {
+        let mut received = String::new();
+        let mut buffer = [0u8; 1024];
+        while !received.contains(needle) {
+            let read = response.read(&mut buffer).unwrap();
+            assert!(read > 0, "stream ended before {:?}: {:?}", needle, received);
+            received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+        }
+        received
+    }
+
+    #[test]
+    fn streams_events_over_http() {
+        let hub = EventHub::new();
+        let server_hub = hub.clone();
+        let server = rouille::Server::new("127.0.0.1:0", move |request| {
+            match server_hub.open_stream(request.header("Last-Event-ID")) {
+                Some(stream) => stream.into_response(),
+                None => Response::empty_400(),
+            }
+        })
+        .unwrap();
+        let addr = server.server_addr();
+        let (_handle, stop) = server.stoppable();
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let mut response = client
+            .get(format!("http://{}/events", addr))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        read_until(&mut response, "retry: 3000\n\n");
+
+        // 单个事件远小于 8 KiB，也应立即送达
+        hub.publish("downloadStart", "{\"gid\":\"a\"}".to_string(), true);
+        let received = read_until(&mut response, "\n\n");
+        assert!(received.contains("event: downloadStart\ndata: {\"gid\":\"a\"}\n\n"));
+
+        drop(response);
+        let _ = stop.send(());
+    }
+
+    #[test]
+    fn parses_event_ids() {
+        assert_eq!(parse_event_id("1700000000-42"), Some((1700000000, 42)));
+        assert_eq!(parse_event_id(" 1-2 "), Some((1, 2)));
+        assert_eq!(parse_event_id("42"), None);
+        assert_eq!(parse_event_id("a-1"), None);
+        assert_eq!(parse_event_id("1--1"), None);
+    }
+
+    #[test]
+    fn replays_events_after_last_event_id() {
+        let hub = EventHub::new();
+        let epoch = hub.inner.0.lock().unwrap().epoch;
+        hub.publish("downloadStart", "1".to_string(), true);
+        hub.publish("stats", "{}".to_string(), false);
+        hub.publish("downloadComplete", "3".to_string(), true);
+
+        let stream = hub.open_stream(Some(&format!("{}-1", epoch))).unwrap();
+        assert_eq!(
+            stream.initial,
+            format!(
+                "retry: {}\n\nid: {}-3\nevent: downloadComplete\ndata: 3\n\n",
+                RETRY_MS, epoch
+            )
+        );
+        assert_eq!(stream.cursor, 3);
+
+        // 没有 Last-Event-ID 时只发送重连间隔
+        let stream = hub.open_stream(None).unwrap();
+        assert_eq!(stream.initial, format!("retry: {}\n\n", RETRY_MS));
+    }
+
+    #[test]
+    fn resets_unknown_or_evicted_event_ids() {
+        let hub = EventHub::new();
+        let epoch = hub.inner.0.lock().unwrap().epoch;
+        for i in 0..REPLAY_CAPACITY + 2 {
+            hub.publish("downloadStart", i.to_string(), true);
+        }
+        let reset = format!(
+            "retry: {}\n\nid: {}-{}\nevent: reset\ndata: {{}}\n\n",
+            RETRY_MS,
+            epoch,
+            REPLAY_CAPACITY + 2
+        );
+        for last_event_id in [
+            // 上一次运行的编号
+            format!("{}-1", epoch - 1),
+            // 已移出保留队列
+            format!("{}-1", epoch),
+            // 尚未产生的编号
+            format!("{}-{}", epoch, REPLAY_CAPACITY + 3),
+            "garbage".to_string(),
+        ] {
+            let stream = hub.open_stream(Some(&last_event_id)).unwrap();
+            assert_eq!(stream.initial, reset, "{}", last_event_id);
+        }
+
+        // 刚好在保留范围的边界上
+        let stream = hub.open_stream(Some(&format!("{}-2", epoch))).unwrap();
+        assert_eq!(
+            stream.initial.matches("event: downloadStart").count(),
+            REPLAY_CAPACITY
+        );
+    }
+
+    #[test]
+    fn limits_open_streams() {
+        let hub = EventHub::new();
+        let streams: Vec<_> = (0..MAX_STREAMS)
+            .map(|_| hub.open_stream(None).unwrap())
+            .collect();
+        assert!(hub.open_stream(None).is_none());
+        drop(streams);
+        assert!(hub.open_stream(None).is_some());
+    }
+
+    #[test]
+    fn resets_a_stream_that_fell_behind_the_replay_buffer() {
+        let hub = EventHub::new();
+        let mut stream = hub.open_stream(None).unwrap();
+        for i in 0..=REPLAY_CAPACITY {
+            hub.publish("downloadStart", i.to_string(), true);
+        }
+        let chunk = stream.next_chunk().unwrap();
+        assert!(chunk.starts_with(&format!(
+            "id: {}-{}\nevent: reset\n",
+            hub.inner.0.lock().unwrap().epoch,
+            REPLAY_CAPACITY + 1
+        )));
+        assert_eq!(
+            chunk.matches("event: downloadStart").count(),
+            REPLAY_CAPACITY
+        );
+    }
+}
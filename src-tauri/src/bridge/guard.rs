@@ -0,0 +1,288 @@
+use crate::bridge::token::BridgeToken;
+use crate::config::settings::DownloadSettings;
+use rouille::{Request, Response};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// 请求体大小上限
+pub const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// `POST /downloads` 的请求体大小上限，Base64 编码的多文件种子可能有几 MiB
+pub const MAX_DOWNLOAD_BODY_BYTES: u64 = 8 * 1024 * 1024;
+
+/// 通过认证的请求：令牌桶容量（允许的突发请求数）和每秒补充的请求数
+const RATE_LIMIT_BURST: f64 = 30.0;
+const RATE_LIMIT_PER_SEC: f64 = 10.0;
+
+/// 未认证或被拒绝的请求单独限流，网页无法借此耗尽已认证调用方的配额
+const UNAUTHENTICATED_BURST: f64 = 30.0;
+const UNAUTHENTICATED_PER_SEC: f64 = 10.0;
+
+/// 允许的 `Host` 头，防止 DNS 重绑定攻击
+const ALLOWED_HOSTS: [&str; 2] = ["127.0.0.1", "localhost"];
+
+/// 被拒绝的原因
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rejection {
+    /// `Host` 头不是本机地址
+    BadHost,
+    /// 浏览器来源不在允许列表中
+    OriginNotAllowed,
+    /// 缺少令牌或令牌错误
+    Unauthorized,
+    /// 请求体超过大小上限
+    PayloadTooLarge,
+    /// 请求过于频繁
+    RateLimited,
+}
+
+impl Rejection {
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Rejection::BadHost | Rejection::OriginNotAllowed => 403,
+            Rejection::Unauthorized => 401,
+            Rejection::PayloadTooLarge => 413,
+            Rejection::RateLimited => 429,
+        }
+    }
+
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Rejection::BadHost => "Host not allowed",
+            Rejection::OriginNotAllowed => "Origin not allowed",
+            Rejection::Unauthorized => "Missing or invalid bearer token",
+            Rejection::PayloadTooLarge => "Request body too large",
+            Rejection::RateLimited => "Too many requests",
+        }
+    }
+
+    pub fn to_response(self) -> Response {
+        let response = Response::json(&serde_json::json!({
+            "status": "error",
+            "message": self.reason()
+        }))
+        .with_status_code(self.status_code());
+        match self {
+            Rejection::Unauthorized => {
+                response.with_additional_header("WWW-Authenticate", "Bearer")
+            }
+            Rejection::RateLimited => response.with_additional_header("Retry-After", "1"),
+            _ => response,
+        }
+    }
+}
+
+/// 令牌桶限流，同一类调用方共享（接口只监听本机地址，按来源 IP 区分没有意义）
+#[derive(Debug)]
+struct RateLimiter {
+    burst: f64,
+    per_sec: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(burst: f64, per_sec: f64) -> Self {
+        Self {
+            burst,
+            per_sec,
+            state: Mutex::new((burst, Instant::now())),
+        }
+    }
+
+    fn try_acquire(&self) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        let (tokens, last) = &mut *state;
+        let now = Instant::now();
+        *tokens =
+            (*tokens + now.duration_since(*last).as_secs_f64() * self.per_sec).min(self.burst);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// 本地 HTTP 接口的访问控制：Host 检查、来源白名单、令牌认证、请求体大小和频率限制
+pub struct BridgeGuard {
+    token: Arc<BridgeToken>,
+    settings_state: Arc<Mutex<DownloadSettings>>,
+    limiter: RateLimiter,
+    unauthenticated_limiter: RateLimiter,
+}
+
+impl BridgeGuard {
+    pub fn new(token: Arc<BridgeToken>, settings_state: Arc<Mutex<DownloadSettings>>) -> Self {
+        Self {
+            token,
+            settings_state,
+            limiter: RateLimiter::new(RATE_LIMIT_BURST, RATE_LIMIT_PER_SEC),
+            unauthenticated_limiter: RateLimiter::new(
+                UNAUTHENTICATED_BURST,
+                UNAUTHENTICATED_PER_SEC,
+            ),
+        }
+    }
+
+    /// 检查请求，通过时返回需要回显给浏览器的 `Origin`（用于 CORS 响应头）
+    ///
+    /// `require_token` 为 false 时不检查令牌，用于健康检查和 CORS 预检请求。
+    /// 通过认证的请求和允许来源的预检请求计入主限流，其余请求（包括被拒绝的）计入单独的限流
+    pub fn check(
+        &self,
+        request: &Request,
+        require_token: bool,
+    ) -> Result<Option<String>, Rejection> {
+        let result = self.check_access(request, require_token);
+        let limiter = match result {
+            Ok(Some(_)) => &self.limiter,
+            Ok(None) if require_token => &self.limiter,
+            _ => &self.unauthenticated_limiter,
+        };
+        if !limiter.try_acquire() {
+            return Err(Rejection::RateLimited);
+        }
+        result
+    }
+
+    fn check_access(
+        &self,
+        request: &Request,
+        require_token: bool,
+    ) -> Result<Option<String>, Rejection> {
+        if let Some(host) = request.header("Host") {
+            let hostname = host.rsplit_once(':').map_or(host, |(name, _)| name);
+            if !ALLOWED_HOSTS.contains(&hostname.to_ascii_lowercase().as_str()) {
+                return Err(Rejection::BadHost);
+            }
+        }
+
+        // 没有 Origin 的请求来自本机的普通程序；浏览器发出的请求必须来自允许的扩展
+        let origin = request.header("Origin").map(|origin| origin.to_string());
+        if let Some(origin) = &origin {
+            let allowed = self
+                .settings_state
+                .lock()
+                .map(|settings| settings.bridge.allows_origin(origin))
+                .unwrap_or(false);
+            if !allowed {
+                return Err(Rejection::OriginNotAllowed);
+            }
+        }
+
+        if request
+            .header("Content-Length")
+            .and_then(|length| length.trim().parse::<u64>().ok())
+            .is_some_and(|length| length > body_limit(request))
+        {
+            return Err(Rejection::PayloadTooLarge);
+        }
+
+        if require_token && !self.is_authorized(request) {
+            return Err(Rejection::Unauthorized);
+        }
+
+        Ok(origin)
+    }
+
+    /// 浏览器的 `EventSource` 不能设置请求头，`/events` 也接受 `access_token` 查询参数
+    fn is_authorized(&self, request: &Request) -> bool {
+        if self.token.verify_header(request.header("Authorization")) {
+            return true;
+        }
+        request.url() == "/events"
+            && request
+                .get_param("access_token")
+                .is_some_and(|token| self.token.verify(&token))
+    }
+}
+
+/// 记录被拒绝的请求
+pub fn log_rejection(request: &Request, rejection: Rejection) {
+    eprintln!(
+        "Rejected bridge request {} {} from {} (origin: {}): {}",
+        request.method(),
+        request.url(),
+        request.remote_addr(),
+        request.header("Origin").unwrap_or("-"),
+        rejection.reason()
+    );
+}
+
+/// 请求路径对应的请求体大小上限
+fn body_limit(request: &Request) -> u64 {
+    match (request.method(), request.url().trim_matches('/')) {
+        ("POST", "downloads") => MAX_DOWNLOAD_BODY_BYTES,
+        _ => MAX_BODY_BYTES,
+    }
+}
+
+/// 读取请求体，最多读取该路径的大小上限，分块传输时同样生效
+pub fn read_body(request: &Request) -> Result<Vec<u8>, Rejection> {
+    let limit = body_limit(request);
+    let mut body = Vec::new();
+    if let Some(data) = request.data() {
+        if data.take(limit + 1).read_to_end(&mut body).is_err() {
+            body.clear();
+        }
+    }
+    if body.len() as u64 > limit {
+        return Err(Rejection::PayloadTooLarge);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, url: &str) -> Request {
+        Request::fake_http(method, url, vec![], vec![])
+    }
+
+    #[test]
+    fn rate_limiter_allows_bursts_then_refills() {
+        let limiter = RateLimiter::new(2.0, 0.0);
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+
+        let limiter = RateLimiter::new(1.0, 1000.0);
+        assert!(limiter.try_acquire());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(limiter.try_acquire());
+    }
+
+    #[test]
+    fn only_download_creation_gets_the_larger_body_limit() {
+        assert_eq!(
+            body_limit(&request("POST", "/downloads")),
+            MAX_DOWNLOAD_BODY_BYTES
+        );
+        assert_eq!(
+            body_limit(&request("POST", "/downloads/")),
+            MAX_DOWNLOAD_BODY_BYTES
+        );
+        assert_eq!(body_limit(&request("PUT", "/downloads")), MAX_BODY_BYTES);
+        assert_eq!(
+            body_limit(&request("POST", "/downloads/abc")),
+            MAX_BODY_BYTES
+        );
+        assert_eq!(body_limit(&request("POST", "/")), MAX_BODY_BYTES);
+    }
+
+    #[test]
+    fn read_body_enforces_the_limit() {
+        let body = vec![b'x'; MAX_BODY_BYTES as usize + 1];
+        let request = Request::fake_http("POST", "/", vec![], body.clone());
+        assert_eq!(read_body(&request), Err(Rejection::PayloadTooLarge));
+
+        let request = Request::fake_http("POST", "/downloads", vec![], body.clone());
+        assert_eq!(read_body(&request).unwrap(), body);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod api;
+pub mod commands;
+pub mod config;
+pub mod events;
+pub mod guard;
+pub mod server;
+pub mod token;
@@ -0,0 +1,122 @@
+use crate::bridge::api::BridgeApi;
+use crate::bridge::guard::{log_rejection, read_body, BridgeGuard};
+use crate::launch::handoff::{handle_handoff, HandoffMessage, HANDOFF_PATH};
+use rouille::{Request, Response};
+use tauri::Emitter;
+
+/// 本地 HTTP 接口的监听地址，只接受本机连接
+pub const BRIDGE_ADDR: &str = "127.0.0.1:6567";
+
+/// 启动本地 HTTP 接口，供浏览器扩展和脚本管理下载任务（阻塞当前线程）
+pub fn start_http_server(app_handle: tauri::AppHandle, guard: BridgeGuard, api: BridgeApi) {
+    rouille::start_server(BRIDGE_ADDR, move |request| {
+        // CORS 预检请求不带令牌，只检查来源
+        let require_token = !matches!(
+            (request.method(), request.url().as_ref()),
+            ("OPTIONS", _) | ("GET", "/health")
+        );
+        let origin = match guard.check(request, require_token) {
+            Ok(origin) => origin,
+            Err(rejection) => {
+                log_rejection(request, rejection);
+                return rejection.to_response();
+            }
+        };
+
+        println!("Received request: {} {}", request.method(), request.url());
+        let response = api
+            .handle(request)
+            .unwrap_or_else(|| route(&app_handle, request));
+        with_cors_headers(response, origin)
+    })
+}
+
+fn route(app_handle: &tauri::AppHandle, request: &Request) -> Response {
+    match (request.method(), request.url().as_ref()) {
+        ("OPTIONS", _) => Response::empty_204(),
+
+        ("GET", "/health") => Response::text("Server is running"),
+
+        ("POST", "/message") => match read_json(request) {
+            // 发送消息到前端
+            Ok(message) => {
+                if let Err(e) = app_handle.emit("http-message", message) {
+                    eprintln!("Failed to emit http-message: {}", e);
+                }
+                Response::json(&serde_json::json!({
+                    "status": "success",
+                    "message": "Message sent to frontend"
+                }))
+            }
+            Err(response) => response,
+        },
+
+        ("POST", "/command") => match read_json(request) {
+            Ok(data) => {
+                if let Err(e) = app_handle.emit("http-command", &data) {
+                    eprintln!("Failed to emit http-command: {}", e);
+                }
+                Response::json(&serde_json::json!({
+                    "status": "success"
+                }))
+            }
+            Err(response) => response,
+        },
+
+        // 第二个实例转交的启动参数
+        ("POST", HANDOFF_PATH) => match read_json(request) {
+            Ok(data) => match serde_json::from_value::<HandoffMessage>(data) {
+                Ok(message) => {
+                    handle_handoff(app_handle, message);
+                    Response::json(&serde_json::json!({
+                        "status": "success"
+                    }))
+                }
+                Err(e) => json_error(&format!("Invalid handoff message: {}", e), 400),
+            },
+            Err(response) => response,
+        },
+
+        _ => Response::text("Not found").with_status_code(404),
+    }
+}
+
+/// 读取 JSON 请求体，失败时返回对应的错误响应
+pub fn read_json(request: &Request) -> Result<serde_json::Value, Response> {
+    let is_json = request
+        .header("Content-Type")
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    if !is_json {
+        return Err(json_error("Expected application/json", 415));
+    }
+
+    let body = read_body(request).map_err(|rejection| {
+        log_rejection(request, rejection);
+        rejection.to_response()
+    })?;
+    serde_json::from_slice(&body).map_err(|_| json_error("Invalid JSON", 400))
+}
+
+fn json_error(message: &str, status_code: u16) -> Response {
+    Response::json(&serde_json::json!({
+        "status": "error",
+        "message": message
+    }))
+    .with_status_code(status_code)
+}
+
+/// 允许的浏览器扩展可以直接读取响应
+fn with_cors_headers(response: Response, origin: Option<String>) -> Response {
+    match origin {
+        Some(origin) => response
+            .with_additional_header("Access-Control-Allow-Origin", origin)
+            .with_additional_header(
+                "Access-Control-Allow-Headers",
+                "Authorization, Content-Type",
+            )
+            .with_additional_header("Access-Control-Allow-Methods", "GET, POST, DELETE, OPTIONS")
+            .with_additional_header("Access-Control-Max-Age", "600")
+            .with_additional_header("Vary", "Origin"),
+        None => response,
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::AppError;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 令牌长度
+const TOKEN_LEN: usize = 48;
+
+/// 应用标识，与 tauri.conf.json 的 `identifier` 一致，Tauri 的应用数据目录以它命名
+const APP_IDENTIFIER: &str = "com.application.dlapp";
+
+/// 令牌文件路径（应用数据目录下的 `bridge_token`）
+///
+/// 第二个实例转交启动参数时还没有 Tauri 上下文，因此直接按规则拼出路径
+pub fn token_path() -> Result<PathBuf, AppError> {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER).join("bridge_token"))
+        .ok_or_else(|| AppError::Settings("Unable to locate the app data directory".to_string()))
+}
+
+/// 读取已有的令牌，不会生成新令牌
+pub fn read_token(path: &Path) -> Result<String, AppError> {
+    let token = std::fs::read_to_string(path)?.trim().to_string();
+    if !is_valid_token(&token) {
+        return Err(AppError::Settings(
+            "Bridge token file is invalid".to_string(),
+        ));
+    }
+    Ok(token)
+}
+
+/// 本地 HTTP 接口的访问令牌，每次安装随机生成，保存在应用数据目录
+///
+/// 调用方需要带上 `Authorization: Bearer <令牌>`，浏览器扩展在设置页中填写该令牌。
+#[derive(Debug)]
+pub struct BridgeToken {
+    path: PathBuf,
+    token: Mutex<String>,
+}
+
+impl BridgeToken {
+    /// 读取令牌文件，不存在或内容无效时生成新令牌
+    pub fn load_or_create(path: PathBuf) -> Result<Self, AppError> {
+        let token = match std::fs::read_to_string(&path) {
+            Ok(content) if is_valid_token(content.trim()) => content.trim().to_string(),
+            Ok(_) => {
+                println!("Bridge token file is invalid, generating a new token");
+                create_token(&path)?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => create_token(&path)?,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            token: Mutex::new(token),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self) -> Result<String, AppError> {
+        Ok(self.token.lock()?.clone())
+    }
+
+    /// 生成新令牌，旧令牌立即失效
+    pub fn regenerate(&self) -> Result<String, AppError> {
+        let token = create_token(&self.path)?;
+        *self.token.lock()? = token.clone();
+        Ok(token)
+    }
+
+    /// 校验 `Authorization` 头
+    pub fn verify_header(&self, header: Option<&str>) -> bool {
+        header
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|candidate| self.verify(candidate))
+    }
+
+    /// 校验令牌，比较时间与令牌内容无关
+    pub fn verify(&self, candidate: &str) -> bool {
+        let Ok(token) = self.token.lock() else {
+            return false;
+        };
+        constant_time_eq(candidate.trim().as_bytes(), token.as_bytes())
+    }
+}
+
+fn is_valid_token(token: &str) -> bool {
+    token.len() == TOKEN_LEN && token.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// 生成令牌并写入文件，Unix 下只允许当前用户读取
+fn create_token(path: &Path) -> Result<String, AppError> {
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // 创建时就限制权限，写入令牌之前其他用户无法打开文件
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // 轮换令牌时文件已存在，mode 只对新建的文件生效
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(token.as_bytes())?;
+
+    Ok(token)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,547 @@
+use crate::aria2c::{Aria2cEvents, Aria2cState, DownloadEvent, DownloadEventKind};
+use crate::error::AppError;
+use crate::history::store::HistoryStore;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Notify;
+
+/// aria2 校验失败的错误码
+const ARIA2_CHECKSUM_ERROR: &str = "32";
+
+/// 读取文件的缓冲区大小
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 两次进度事件之间至少间隔的字节数
+const PROGRESS_STEP: u64 = 16 * 1024 * 1024;
+
+/// 校验结果的保留时间，没有完成处理等待的结果过期后清理
+const OUTCOME_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// 完成处理等待校验器开始校验的最长时间
+const VERIFY_START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 完成处理等待校验结束的最长时间
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// 支持的校验算法，名称与 aria2 的 `checksum` 选项一致
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ChecksumAlgorithm {
+    #[serde(rename = "md5")]
+    Md5,
+    #[serde(rename = "sha-1")]
+    Sha1,
+    #[serde(rename = "sha-256")]
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Md5 => "md5",
+            ChecksumAlgorithm::Sha1 => "sha-1",
+            ChecksumAlgorithm::Sha256 => "sha-256",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "md5" => Some(ChecksumAlgorithm::Md5),
+            "sha-1" | "sha1" => Some(ChecksumAlgorithm::Sha1),
+            "sha-256" | "sha256" => Some(ChecksumAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    /// 十六进制摘要的长度
+    fn digest_len(&self) -> usize {
+        match self {
+            ChecksumAlgorithm::Md5 => 32,
+            ChecksumAlgorithm::Sha1 => 40,
+            ChecksumAlgorithm::Sha256 => 64,
+        }
+    }
+}
+
+/// 新建任务时指定的期望摘要，反序列化时校验摘要格式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ChecksumInput")]
+pub struct ExpectedChecksum {
+    pub algorithm: ChecksumAlgorithm,
+    /// 小写十六进制
+    pub digest: String,
+}
+
+#[derive(Deserialize)]
+struct ChecksumInput {
+    algorithm: ChecksumAlgorithm,
+    digest: String,
+}
+
+impl TryFrom<ChecksumInput> for ExpectedChecksum {
+    type Error = String;
+
+    fn try_from(input: ChecksumInput) -> Result<Self, Self::Error> {
+        let digest = input.digest.trim().to_ascii_lowercase();
+        if digest.len() != input.algorithm.digest_len()
+            || !digest.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(format!(
+                "Invalid {} digest: expected {} hex characters",
+                input.algorithm.name(),
+                input.algorithm.digest_len()
+            ));
+        }
+        Ok(Self {
+            algorithm: input.algorithm,
+            digest,
+        })
+    }
+}
+
+impl ExpectedChecksum {
+    /// aria2 `checksum` 选项的值，格式为 `<算法>=<摘要>`
+    pub fn to_aria2c_option(&self) -> String {
+        format!("{}={}", self.algorithm.name(), self.digest)
+    }
+
+    /// 解析 aria2 `getOption` 返回的 `checksum` 选项
+    pub fn from_aria2c_option(value: &str) -> Option<Self> {
+        let (algorithm, digest) = value.split_once('=')?;
+        let input = ChecksumInput {
+            algorithm: ChecksumAlgorithm::from_name(algorithm)?,
+            digest: digest.to_string(),
+        };
+        Self::try_from(input).ok()
+    }
+}
+
+/// 校验进度，通过 `checksum-progress` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+struct ChecksumProgress {
+    gid: String,
+    verified_length: u64,
+    total_length: u64,
+}
+
+/// 校验结果，通过 `checksum-verified` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+struct ChecksumResult {
+    gid: String,
+    path: String,
+    algorithm: ChecksumAlgorithm,
+    expected: String,
+    actual: Option<String>,
+    matched: bool,
+    error: Option<String>,
+}
+
+/// 任务完成后的校验结果，完成处理据此决定是否继续
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChecksumOutcome {
+    /// 没有设置期望摘要，或无需本程序校验
+    Skipped,
+    Matched,
+    Mismatched,
+    /// 无法完成校验（读取文件失败、aria2c 不可用等）
+    Failed,
+}
+
+/// 校验器和完成处理之间的同步：完成处理需要等待校验结束，
+/// 否则移动或解压可能在计算摘要时改动文件
+#[derive(Clone, Default)]
+pub struct ChecksumOutcomes {
+    finished: Arc<Mutex<HashMap<String, OutcomeEntry>>>,
+    notify: Arc<Notify>,
+}
+
+struct OutcomeEntry {
+    /// `None` 表示校验器已经开始校验，尚未得到结果
+    outcome: Option<ChecksumOutcome>,
+    at: Instant,
+}
+
+impl ChecksumOutcomes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 校验器收到完成通知，开始校验
+    fn begin(&self, gid: &str) {
+        self.set(gid, None);
+    }
+
+    fn record(&self, gid: &str, outcome: ChecksumOutcome) {
+        self.set(gid, Some(outcome));
+    }
+
+    fn set(&self, gid: &str, outcome: Option<ChecksumOutcome>) {
+        if let Ok(mut finished) = self.finished.lock() {
+            finished.retain(|_, entry| entry.at.elapsed() < OUTCOME_TTL);
+            let entry = OutcomeEntry {
+                outcome,
+                at: Instant::now(),
+            };
+            finished.insert(gid.to_string(), entry);
+        }
+        self.notify.notify_waiters();
+    }
+
+    /// 等待任务的校验结束并取走结果
+    ///
+    /// 校验器漏掉了完成通知（事件积压）或校验超时都按 `Failed` 处理，不会一直阻塞完成处理
+    pub async fn wait(&self, gid: &str) -> ChecksumOutcome {
+        self.wait_for(gid, VERIFY_START_TIMEOUT, VERIFY_TIMEOUT)
+            .await
+    }
+
+    async fn wait_for(
+        &self,
+        gid: &str,
+        start_timeout: Duration,
+        timeout: Duration,
+    ) -> ChecksumOutcome {
+        let waited = Instant::now();
+        loop {
+            // 先注册等待再检查结果，避免错过检查之后的通知
+            let notified = self.notify.notified();
+            let (outcome, running) = match self.finished.lock() {
+                Ok(mut finished) => match finished.get(gid).map(|entry| entry.outcome) {
+                    Some(None) => (None, true),
+                    Some(outcome) => {
+                        finished.remove(gid);
+                        (outcome, false)
+                    }
+                    None => (None, false),
+                },
+                Err(_) => (Some(ChecksumOutcome::Failed), false),
+            };
+            if let Some(outcome) = outcome {
+                return outcome;
+            }
+
+            let limit = if running { timeout } else { start_timeout };
+            let Some(remaining) = limit.checked_sub(waited.elapsed()) else {
+                eprintln!("Timed out waiting for checksum verification of {}", gid);
+                return ChecksumOutcome::Failed;
+            };
+            // 超时后回到循环开头再检查一次结果
+            let _ = tokio::time::timeout(remaining, notified).await;
+        }
+    }
+}
+
+/// 计算文件摘要，每读取一段调用一次 `progress(已读取, 总大小)`
+pub fn hash_file(
+    path: &Path,
+    algorithm: ChecksumAlgorithm,
+    progress: impl FnMut(u64, u64),
+) -> Result<String, AppError> {
+    match algorithm {
+        ChecksumAlgorithm::Md5 => hash_with::<md5::Md5>(path, progress),
+        ChecksumAlgorithm::Sha1 => hash_with::<sha1::Sha1>(path, progress),
+        ChecksumAlgorithm::Sha256 => hash_with::<sha2::Sha256>(path, progress),
+    }
+}
+
+fn hash_with<D: Digest>(
+    path: &Path,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<String, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let total = file.metadata()?.len();
+    let mut hasher = D::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    let mut verified = 0u64;
+    let mut reported = 0u64;
+
+    progress(0, total);
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        verified += read as u64;
+        if verified - reported >= PROGRESS_STEP {
+            progress(verified, total);
+            reported = verified;
+        }
+    }
+    progress(verified, total);
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// 订阅下载通知，对设置了 `checksum` 的任务在完成后由本程序再次校验
+///
+/// aria2 校验失败时任务以错误码 32 结束，此时同样计算实际摘要，记录到历史中；
+/// 成功完成的任务的校验结果记录到 `outcomes`，供完成处理等待
+pub fn start_checksum_verifier(
+    app_handle: tauri::AppHandle,
+    aria2c_state: Aria2cState,
+    events: Aria2cEvents,
+    history: HistoryStore,
+    outcomes: ChecksumOutcomes,
+) {
+    let mut receiver = events.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if !matches!(
+                        event.kind,
+                        DownloadEventKind::Complete | DownloadEventKind::Error
+                    ) {
+                        continue;
+                    }
+                    if event.kind == DownloadEventKind::Complete {
+                        outcomes.begin(&event.gid);
+                    }
+                    // 每个任务单独校验，大文件不会阻塞后续通知
+                    let app_handle = app_handle.clone();
+                    let aria2c_state = aria2c_state.clone();
+                    let history = history.clone();
+                    let outcomes = outcomes.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let outcome =
+                            match verify_download(&app_handle, &aria2c_state, &history, &event)
+                                .await
+                            {
+                                Ok(outcome) => outcome,
+                                Err(e) => {
+                                    eprintln!("Failed to verify checksum of {}: {}", event.gid, e);
+                                    ChecksumOutcome::Failed
+                                }
+                            };
+                        if event.kind == DownloadEventKind::Complete {
+                            outcomes.record(&event.gid, outcome);
+                        }
+                    });
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    eprintln!("Checksum verifier lagged, {} events skipped", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+async fn verify_download(
+    app_handle: &tauri::AppHandle,
+    aria2c_state: &Aria2cState,
+    history: &HistoryStore,
+    event: &DownloadEvent,
+) -> Result<ChecksumOutcome, AppError> {
+    // 外部守护进程下载的文件本程序不一定能读取
+    if aria2c_state.endpoint()?.external {
+        return Ok(ChecksumOutcome::Skipped);
+    }
+
+    let client = aria2c_state.client()?;
+    let options = client.get_option(&event.gid).await?;
+    let Some(expected) = options
+        .get("checksum")
+        .and_then(|value| ExpectedChecksum::from_aria2c_option(value))
+    else {
+        return Ok(ChecksumOutcome::Skipped);
+    };
+
+    let keys = ["errorCode", "files"]
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    let status = client.tell_status(&event.gid, Some(keys)).await?;
+    if event.kind == DownloadEventKind::Error
+        && status.get("errorCode").and_then(|v| v.as_str()) != Some(ARIA2_CHECKSUM_ERROR)
+    {
+        return Ok(ChecksumOutcome::Skipped);
+    }
+    let Some(path) = selected_file(&status) else {
+        eprintln!(
+            "Cannot verify checksum of {}: expected exactly one selected file",
+            event.gid
+        );
+        return Ok(ChecksumOutcome::Failed);
+    };
+
+    let progress_handle = app_handle.clone();
+    let gid = event.gid.clone();
+    let hash_path = path.clone();
+    let algorithm = expected.algorithm;
+    let actual = tokio::task::spawn_blocking(move || {
+        hash_file(&hash_path, algorithm, |verified_length, total_length| {
+            let progress = ChecksumProgress {
+                gid: gid.clone(),
+                verified_length,
+                total_length,
+            };
+            if let Err(e) = progress_handle.emit("checksum-progress", &progress) {
+                eprintln!("Failed to emit checksum-progress: {}", e);
+            }
+        })
+    })
+    .await
+    .map_err(|e| AppError::Process(format!("Checksum task failed: {}", e)))?;
+
+    let mut result = ChecksumResult {
+        gid: event.gid.clone(),
+        path: path.to_string_lossy().to_string(),
+        algorithm: expected.algorithm,
+        expected: expected.digest.clone(),
+        actual: None,
+        matched: false,
+        error: None,
+    };
+    match actual {
+        Ok(actual) => {
+            result.matched = actual == expected.digest;
+            if !result.matched {
+                history.record_checksum_mismatch(
+                    &event.gid,
+                    expected.algorithm.name(),
+                    &expected.digest,
+                    &actual,
+                )?;
+            }
+            result.actual = Some(actual);
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+
+    if let Err(e) = app_handle.emit("checksum-verified", &result) {
+        eprintln!("Failed to emit checksum-verified: {}", e);
+    }
+    Ok(match (result.matched, result.error) {
+        (true, _) => ChecksumOutcome::Matched,
+        (false, None) => ChecksumOutcome::Mismatched,
+        (false, Some(_)) => ChecksumOutcome::Failed,
+    })
+}
+
+/// 期望摘要对应的文件：任务中唯一被选中的文件，选中多个文件时无法确定校验哪一个
+fn selected_file(status: &serde_json::Value) -> Option<PathBuf> {
+    let files = status.get("files")?.as_array()?;
+    let mut selected = files
+        .iter()
+        .filter(|file| file.get("selected").and_then(|v| v.as_str()) != Some("false"))
+        .filter_map(|file| file.get("path").and_then(|v| v.as_str()))
+        .filter(|path| !path.is_empty());
+    match (selected.next(), selected.next()) {
+        (Some(path), None) => Some(PathBuf::from(path)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHORT: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn parses_aria2c_checksum_option() {
+        let expected =
+            ExpectedChecksum::from_aria2c_option("SHA1=A9993E364706816ABA3E25717850C26C9CD0D89D")
+                .unwrap();
+        assert_eq!(expected.algorithm, ChecksumAlgorithm::Sha1);
+        assert_eq!(expected.digest, "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            expected.to_aria2c_option(),
+            "sha-1=a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+
+        // 长度不符、非十六进制、不支持的算法
+        assert!(ExpectedChecksum::from_aria2c_option("md5=abc").is_none());
+        assert!(ExpectedChecksum::from_aria2c_option(&format!("md5={}", "g".repeat(32))).is_none());
+        assert!(
+            ExpectedChecksum::from_aria2c_option(&format!("sha-512={}", "0".repeat(128))).is_none()
+        );
+        assert!(ExpectedChecksum::from_aria2c_option("").is_none());
+    }
+
+    #[test]
+    fn hashes_known_vectors() {
+        let path = std::env::temp_dir().join(format!("dlapp-checksum-{}", std::process::id()));
+        std::fs::write(&path, b"abc").unwrap();
+        let mut last = None;
+        let cases = [
+            (ChecksumAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (
+                ChecksumAlgorithm::Sha1,
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+            ),
+            (
+                ChecksumAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+        ];
+        for (algorithm, digest) in cases {
+            let actual = hash_file(&path, algorithm, |verified, total| {
+                last = Some((verified, total))
+            });
+            assert_eq!(actual.unwrap(), digest);
+            assert_eq!(last, Some((3, 3)));
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert!(hash_file(&path, ChecksumAlgorithm::Md5, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn picks_the_only_selected_file() {
+        let status = serde_json::json!({ "files": [
+            { "path": "/d/a", "selected": "false" },
+            { "path": "/d/b", "selected": "true" },
+        ]});
+        assert_eq!(selected_file(&status), Some(PathBuf::from("/d/b")));
+
+        let status = serde_json::json!({ "files": [
+            { "path": "/d/a", "selected": "true" },
+            { "path": "/d/b", "selected": "true" },
+        ]});
+        assert_eq!(selected_file(&status), None);
+        assert_eq!(
+            selected_file(&serde_json::json!({ "files": [{ "path": "" }] })),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_returns_a_recorded_outcome() {
+        let outcomes = ChecksumOutcomes::new();
+        outcomes.begin("a");
+        let recorder = outcomes.clone();
+        tokio::spawn(async move { recorder.record("a", ChecksumOutcome::Matched) });
+        assert_eq!(
+            outcomes.wait_for("a", SHORT, Duration::from_secs(5)).await,
+            ChecksumOutcome::Matched
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_does_not_block_on_a_missing_outcome() {
+        let outcomes = ChecksumOutcomes::new();
+        // 校验器没有收到通知
+        assert_eq!(
+            outcomes.wait_for("a", SHORT, Duration::from_secs(60)).await,
+            ChecksumOutcome::Failed
+        );
+        // 校验器开始后没有结束
+        outcomes.begin("b");
+        assert_eq!(
+            outcomes.wait_for("b", Duration::ZERO, SHORT).await,
+            ChecksumOutcome::Failed
+        );
+    }
+}
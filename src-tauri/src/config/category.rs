@@ -0,0 +1,265 @@
+use crate::aria2c::download_manager::TaskKind;
+use crate::error::AppError;
+use crate::torrent::magnet::MagnetLink;
+use crate::torrent::meta::TorrentMeta;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 下载分类：满足任一规则的新任务自动保存到分类目录，并使用分类的限速和连接数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Category {
+    /// 分类名称，例如 "视频"
+    pub name: String,
+    /// 保存目录
+    pub dir: String,
+    /// 匹配规则，为空时只能在添加任务时手动指定
+    #[serde(default)]
+    pub rules: Vec<CategoryRule>,
+    /// 下载速度限制 (bytes/s)
+    #[serde(default)]
+    pub max_download_speed: Option<u64>,
+    /// 上传速度限制 (bytes/s)
+    #[serde(default)]
+    pub max_upload_speed: Option<u64>,
+    /// 连接数
+    #[serde(default)]
+    pub max_connections: Option<u32>,
+}
+
+/// 分类的匹配规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CategoryRule {
+    /// 文件扩展名 (不带点，不区分大小写)，支持 "tar.gz" 这样的多段扩展名
+    Extension { extensions: Vec<String> },
+    /// 来源域名，同时匹配子域名
+    Host { hosts: Vec<String> },
+    /// 任务类型：种子/磁力或 HTTP/FTP
+    Kind { kind: TaskKind },
+}
+
+/// 新任务用于匹配分类的信息
+#[derive(Debug, Clone)]
+pub struct TaskDescriptor {
+    pub kind: TaskKind,
+    /// 文件名
+    pub names: Vec<String>,
+    /// 来源域名
+    pub hosts: Vec<String>,
+}
+
+impl TaskDescriptor {
+    /// HTTP/FTP 地址，文件名取路径的最后一段；磁力链接按磁力链接处理
+    pub fn from_uris(uris: &[String]) -> Self {
+        if let Some(magnet) = uris.iter().find_map(|uri| MagnetLink::parse(uri).ok()) {
+            return Self::from_magnet(&magnet);
+        }
+
+        let mut descriptor = Self {
+            kind: TaskKind::Http,
+            names: Vec::new(),
+            hosts: Vec::new(),
+        };
+        for url in uris.iter().filter_map(|uri| url::Url::parse(uri).ok()) {
+            if let Some(host) = url.host_str() {
+                descriptor.hosts.push(host.to_ascii_lowercase());
+            }
+            if let Some(name) = url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .filter(|name| !name.is_empty())
+            {
+                descriptor.names.push(name.to_string());
+            }
+        }
+        descriptor
+    }
+
+    /// 磁力链接，文件名取显示名称
+    pub fn from_magnet(magnet: &MagnetLink) -> Self {
+        Self {
+            kind: TaskKind::Torrent,
+            names: magnet.display_name.iter().cloned().collect(),
+            hosts: Vec::new(),
+        }
+    }
+
+    /// 种子文件，按最大的文件判断类型，避免被附带的 .nfo、.txt 等小文件干扰
+    pub fn from_torrent(meta: &TorrentMeta) -> Self {
+        let names = meta
+            .files
+            .iter()
+            .filter(|file| !file.padding)
+            .max_by_key(|file| file.length)
+            .and_then(|file| file.path.last().cloned())
+            .into_iter()
+            .collect();
+        Self {
+            kind: TaskKind::Torrent,
+            names,
+            hosts: Vec::new(),
+        }
+    }
+
+    /// Metalink 在 aria2 解析之前不知道文件名，只能按类型匹配
+    pub fn metalink() -> Self {
+        Self {
+            kind: TaskKind::Http,
+            names: Vec::new(),
+            hosts: Vec::new(),
+        }
+    }
+}
+
+impl CategoryRule {
+    pub fn matches(&self, task: &TaskDescriptor) -> bool {
+        match self {
+            CategoryRule::Extension { extensions } => task.names.iter().any(|name| {
+                let name = name.to_ascii_lowercase();
+                extensions.iter().any(|ext| {
+                    let ext = ext.trim_start_matches('.').to_ascii_lowercase();
+                    !ext.is_empty() && name.ends_with(&format!(".{}", ext))
+                })
+            }),
+            CategoryRule::Host { hosts } => task.hosts.iter().any(|host| {
+                hosts.iter().any(|rule| {
+                    let rule = rule.trim().to_ascii_lowercase();
+                    !rule.is_empty() && (*host == rule || host.ends_with(&format!(".{}", rule)))
+                })
+            }),
+            CategoryRule::Kind { kind } => task.kind == *kind,
+        }
+    }
+}
+
+impl Category {
+    pub fn matches(&self, task: &TaskDescriptor) -> bool {
+        self.rules.iter().any(|rule| rule.matches(task))
+    }
+
+    /// 分类对应的 aria2c 选项
+    pub fn to_aria2c_options(&self) -> HashMap<String, String> {
+        let mut options = HashMap::new();
+        options.insert("dir".to_string(), self.dir.clone());
+        if let Some(speed) = self.max_download_speed {
+            options.insert("max-download-limit".to_string(), speed.to_string());
+        }
+        if let Some(speed) = self.max_upload_speed {
+            options.insert("max-upload-limit".to_string(), speed.to_string());
+        }
+        if let Some(connections) = self.max_connections {
+            options.insert(
+                "max-connection-per-server".to_string(),
+                connections.to_string(),
+            );
+            options.insert("split".to_string(), connections.to_string());
+        }
+        options
+    }
+}
+
+/// 校验分类列表：名称和目录不能为空，名称不能重复
+pub fn validate_categories(categories: &[Category]) -> Result<(), AppError> {
+    for (index, category) in categories.iter().enumerate() {
+        if category.name.trim().is_empty() {
+            return Err(AppError::InvalidInput(
+                "Category name must not be empty".to_string(),
+            ));
+        }
+        if category.dir.trim().is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "Category \"{}\" requires a directory",
+                category.name
+            )));
+        }
+        if categories[..index]
+            .iter()
+            .any(|other| other.name == category.name)
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Duplicate category name \"{}\"",
+                category.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http(uris: &[&str]) -> TaskDescriptor {
+        TaskDescriptor::from_uris(&uris.iter().map(|uri| uri.to_string()).collect::<Vec<_>>())
+    }
+
+    fn category(name: &str, rules: Vec<CategoryRule>) -> Category {
+        Category {
+            name: name.to_string(),
+            dir: format!("/downloads/{}", name),
+            rules,
+            max_download_speed: None,
+            max_upload_speed: None,
+            max_connections: None,
+        }
+    }
+
+    #[test]
+    fn matches_extensions_case_insensitively() {
+        let rule = CategoryRule::Extension {
+            extensions: vec![".MKV".to_string(), "tar.gz".to_string(), "".to_string()],
+        };
+        assert!(rule.matches(&http(&["https://example.com/a/Movie.mkv"])));
+        assert!(rule.matches(&http(&["https://example.com/src.TAR.GZ"])));
+        assert!(!rule.matches(&http(&["https://example.com/archive.gz"])));
+        assert!(!rule.matches(&http(&["https://example.com/mkv"])));
+        // 空扩展名不匹配任何文件
+        assert!(!rule.matches(&http(&["https://example.com/README"])));
+    }
+
+    #[test]
+    fn matches_hosts_on_label_boundaries() {
+        let rule = CategoryRule::Host {
+            hosts: vec!["Example.com".to_string()],
+        };
+        assert!(rule.matches(&http(&["https://example.com/a.iso"])));
+        assert!(rule.matches(&http(&["https://CDN.example.com/a.iso"])));
+        assert!(!rule.matches(&http(&["https://badexample.com/a.iso"])));
+        assert!(!rule.matches(&http(&["https://example.com.evil.net/a.iso"])));
+    }
+
+    #[test]
+    fn matches_task_kinds() {
+        let rule = CategoryRule::Kind {
+            kind: TaskKind::Torrent,
+        };
+        let magnet =
+            http(&["magnet:?xt=urn:btih:ca41b533e1b532b4d8d6f8db8e18b0d3d26ea1b7&dn=a.iso"]);
+        assert_eq!(magnet.names, vec!["a.iso"]);
+        assert!(rule.matches(&magnet));
+        assert!(!rule.matches(&http(&["ftp://example.com/a.iso"])));
+        assert!(!category("manual", vec![]).matches(&magnet));
+    }
+
+    #[test]
+    fn validates_categories() {
+        assert!(
+            validate_categories(&[category("video", vec![]), category("music", vec![])]).is_ok()
+        );
+
+        let mut unnamed = category("video", vec![]);
+        unnamed.name = " ".to_string();
+        let mut no_dir = category("video", vec![]);
+        no_dir.dir = String::new();
+        for categories in [
+            vec![unnamed],
+            vec![no_dir],
+            vec![category("video", vec![]), category("video", vec![])],
+        ] {
+            assert!(matches!(
+                validate_categories(&categories),
+                Err(AppError::InvalidInput(_))
+            ));
+        }
+    }
+}
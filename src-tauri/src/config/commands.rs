@@ -1,4 +1,6 @@
+use crate::config::category::{validate_categories, Category};
 use crate::config::settings::DownloadSettings;
+use crate::error::AppError;
 use std::sync::{Arc, Mutex};
 use serde::{Serialize};
 
@@ -14,10 +16,8 @@ pub struct DiskInfo {
 #[tauri::command]
 pub async fn get_download_settings(
     settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
-) -> Result<DownloadSettings, String> {
-    let settings = settings_state
-        .lock()
-        .map_err(|e| format!("无法获取设置锁: {}", e))?;
+) -> Result<DownloadSettings, AppError> {
+    let settings = settings_state.lock()?;
     Ok(settings.clone())
 }
 
@@ -30,10 +30,8 @@ pub async fn update_download_settings(
     max_upload_speed: Option<u64>,
     max_concurrent_downloads: Option<u32>,
     max_connections_per_task: Option<u32>,
-) -> Result<DownloadSettings, String> {
-    let mut settings = settings_state
-        .lock()
-        .map_err(|e| format!("无法获取设置锁: {}", e))?;
+) -> Result<DownloadSettings, AppError> {
+    let mut settings = settings_state.lock()?;
 
     settings.update_global_settings(
         default_download_dir,
@@ -49,3 +47,43 @@ pub async fn update_download_settings(
     Ok(settings.clone())
 }
 
+
+/// 更新 aria2c 进程设置（RPC 端口、关闭等待时间）
+#[tauri::command]
+pub async fn update_daemon_settings(
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    rpc_port: Option<u16>,
+    shutdown_timeout_secs: Option<u64>,
+) -> Result<DownloadSettings, AppError> {
+    let mut settings = settings_state.lock()?;
+
+    settings.update_daemon_settings(rpc_port, shutdown_timeout_secs);
+
+    settings.save()?;
+
+    println!("aria2c 进程设置已更新: {:?}", settings);
+    Ok(settings.clone())
+}
+
+/// 获取下载分类
+#[tauri::command]
+pub async fn get_categories(
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<Vec<Category>, AppError> {
+    Ok(settings_state.lock()?.categories.clone())
+}
+
+/// 更新下载分类，列表顺序即匹配顺序
+#[tauri::command]
+pub async fn update_categories(
+    categories: Vec<Category>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<Vec<Category>, AppError> {
+    validate_categories(&categories)?;
+
+    let mut settings = settings_state.lock()?;
+    settings.categories = categories.clone();
+    settings.save()?;
+
+    Ok(categories)
+}
@@ -1,11 +1,21 @@
+use crate::bridge::config::BridgeSettings;
+use crate::checksum::ExpectedChecksum;
+use crate::config::category::{Category, TaskDescriptor};
+use crate::error::AppError;
+use crate::hooks::config::CompletionHooks;
+use crate::schedule::queue::QueueSchedule;
+use crate::schedule::speed::{SpeedLimits, SpeedSchedule};
+use chrono::NaiveDateTime;
 use dirs;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// 下载设置配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DownloadSettings {
     /// 默认下载目录
     pub default_download_dir: String,
@@ -19,10 +29,104 @@ pub struct DownloadSettings {
     pub max_connections_per_task: u32,
     /// 任务级别的自定义设置
     pub task_settings: HashMap<String, TaskSettings>,
+    /// aria2c RPC 首选监听端口，被占用时启动时会改用空闲端口
+    #[serde(default = "default_rpc_port")]
+    pub rpc_port: u16,
+    /// aria2c RPC 密钥，每次安装首次运行时随机生成
+    #[serde(default)]
+    pub rpc_secret: String,
+    /// 外部 aria2 守护进程，配置后不再启动本地 aria2c
+    #[serde(default)]
+    pub external_daemon: Option<ExternalDaemonSettings>,
+    /// 关闭 aria2c 时等待其保存会话并退出的时间 (秒)，超时后强制结束进程
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// 按时间段切换的全局限速
+    #[serde(default)]
+    pub speed_schedule: SpeedSchedule,
+    /// 队列的定时启停计划
+    #[serde(default)]
+    pub queue_schedule: QueueSchedule,
+    /// 任务完成后的处理
+    #[serde(default)]
+    pub completion_hooks: CompletionHooks,
+    /// 下载分类，按顺序匹配，第一个满足规则的分类生效
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    /// 本地 HTTP 接口
+    #[serde(default)]
+    pub bridge: BridgeSettings,
+}
+
+/// 外部 aria2 守护进程（NAS、seedbox 等）的连接设置
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExternalDaemonSettings {
+    /// RPC 地址，支持 http/https/ws/wss，例如 http://nas.local:6800/jsonrpc
+    pub rpc_url: String,
+    /// RPC 密钥
+    pub rpc_secret: String,
+}
+
+/// 日志中代替 RPC 密钥的内容
+const REDACTED: &str = "<redacted>";
+
+// 设置会被打印到日志，手动实现 Debug 以隐藏 RPC 密钥
+impl fmt::Debug for DownloadSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DownloadSettings")
+            .field("default_download_dir", &self.default_download_dir)
+            .field("max_download_speed", &self.max_download_speed)
+            .field("max_upload_speed", &self.max_upload_speed)
+            .field("max_concurrent_downloads", &self.max_concurrent_downloads)
+            .field("max_connections_per_task", &self.max_connections_per_task)
+            .field("task_settings", &self.task_settings)
+            .field("rpc_port", &self.rpc_port)
+            .field("rpc_secret", &REDACTED)
+            .field("external_daemon", &self.external_daemon)
+            .field("shutdown_timeout_secs", &self.shutdown_timeout_secs)
+            .field("speed_schedule", &self.speed_schedule)
+            .field("queue_schedule", &self.queue_schedule)
+            .field("completion_hooks", &self.completion_hooks)
+            .field("categories", &self.categories)
+            .field("bridge", &self.bridge)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ExternalDaemonSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalDaemonSettings")
+            .field("rpc_url", &self.rpc_url)
+            .field("rpc_secret", &REDACTED)
+            .finish()
+    }
+}
+
+/// aria2c 默认的 RPC 端口
+pub const DEFAULT_RPC_PORT: u16 = 6800;
+
+fn default_rpc_port() -> u16 {
+    DEFAULT_RPC_PORT
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    10
+}
+
+/// 生成随机的 RPC 密钥
+fn generate_rpc_secret() -> String {
+    use rand::distributions::Alphanumeric;
+    use rand::Rng;
+
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
 }
 
 /// 单个任务的设置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TaskSettings {
     /// 任务专用下载目录
     pub download_dir: Option<String>,
@@ -32,6 +136,69 @@ pub struct TaskSettings {
     pub max_upload_speed: Option<u64>,
     /// 任务专用连接数
     pub max_connections: Option<u32>,
+    /// 做种分享率，达到后停止做种 (0 表示无限做种)
+    #[serde(default)]
+    pub seed_ratio: Option<f64>,
+    /// 做种时间 (分钟)，达到后停止做种
+    #[serde(default)]
+    pub seed_time: Option<u64>,
+}
+
+impl TaskSettings {
+    /// 用新的设置覆盖已有设置，未指定的字段保持不变
+    pub fn merge(&mut self, other: &TaskSettings) {
+        if other.download_dir.is_some() {
+            self.download_dir = other.download_dir.clone();
+        }
+        if other.max_download_speed.is_some() {
+            self.max_download_speed = other.max_download_speed;
+        }
+        if other.max_upload_speed.is_some() {
+            self.max_upload_speed = other.max_upload_speed;
+        }
+        if other.max_connections.is_some() {
+            self.max_connections = other.max_connections;
+        }
+        if other.seed_ratio.is_some() {
+            self.seed_ratio = other.seed_ratio;
+        }
+        if other.seed_time.is_some() {
+            self.seed_time = other.seed_time;
+        }
+    }
+
+    /// 转换为 aria2.changeOption 的选项
+    ///
+    /// 修改活动任务的 `dir` 会让 aria2 重新开始下载，重新应用设置时应传入 `include_dir = false`
+    pub fn to_aria2c_options(&self, include_dir: bool) -> HashMap<String, String> {
+        let mut options = HashMap::new();
+
+        if include_dir {
+            if let Some(dir) = &self.download_dir {
+                options.insert("dir".to_string(), dir.clone());
+            }
+        }
+        if let Some(speed) = self.max_download_speed {
+            options.insert("max-download-limit".to_string(), speed.to_string());
+        }
+        if let Some(speed) = self.max_upload_speed {
+            options.insert("max-upload-limit".to_string(), speed.to_string());
+        }
+        if let Some(connections) = self.max_connections {
+            options.insert(
+                "max-connection-per-server".to_string(),
+                connections.to_string(),
+            );
+        }
+        if let Some(ratio) = self.seed_ratio {
+            options.insert("seed-ratio".to_string(), ratio.to_string());
+        }
+        if let Some(time) = self.seed_time {
+            options.insert("seed-time".to_string(), time.to_string());
+        }
+
+        options
+    }
 }
 
 /// 新建下载任务的设置
@@ -45,6 +212,12 @@ pub struct NewTaskSettings {
     pub max_upload_speed: Option<u64>,
     /// 连接数限制
     pub max_connections: Option<u32>,
+    /// 期望的文件摘要，下载完成后校验
+    #[serde(default)]
+    pub checksum: Option<ExpectedChecksum>,
+    /// 指定分类名称，不按规则自动匹配
+    #[serde(default)]
+    pub category: Option<String>,
 }
 
 impl Default for DownloadSettings {
@@ -59,27 +232,40 @@ impl Default for DownloadSettings {
             max_concurrent_downloads: 5,
             max_connections_per_task: 16,
             task_settings: HashMap::new(),
+            rpc_port: DEFAULT_RPC_PORT,
+            rpc_secret: generate_rpc_secret(),
+            external_daemon: None,
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+            speed_schedule: SpeedSchedule::default(),
+            queue_schedule: QueueSchedule::default(),
+            completion_hooks: CompletionHooks::default(),
+            categories: Vec::new(),
+            bridge: BridgeSettings::default(),
         }
     }
 }
 
 impl DownloadSettings {
     /// 获取配置文件路径
-    pub fn get_config_path() -> Result<PathBuf, String> {
-        let app_data_dir = dirs::data_dir().ok_or("无法获取应用数据目录")?;
+    pub fn get_config_path() -> Result<PathBuf, AppError> {
+        let app_data_dir = dirs::data_dir().ok_or_else(|| {
+            AppError::Settings("Unable to locate the app data directory".to_string())
+        })?;
 
         let config_dir = app_data_dir.join("com.lixxix.dlapp");
 
         // 确保目录存在
         if !config_dir.exists() {
-            fs::create_dir_all(&config_dir).map_err(|e| format!("无法创建配置目录: {}", e))?;
+            fs::create_dir_all(&config_dir).map_err(|e| {
+                AppError::Settings(format!("Failed to create config directory: {}", e))
+            })?;
         }
 
         Ok(config_dir.join("download_settings.json"))
     }
 
     /// 从文件加载配置
-    pub fn load() -> Result<Self, String> {
+    pub fn load() -> Result<Self, AppError> {
         let config_path = Self::get_config_path()?;
         println!("conig path : {}", config_path.display());
 
@@ -90,23 +276,45 @@ impl DownloadSettings {
             return Ok(default_settings);
         }
 
-        let content =
-            fs::read_to_string(&config_path).map_err(|e| format!("无法读取配置文件: {}", e))?;
-        println!("config path : {}", content);  
-        let settings: Self =
-            serde_json::from_str(&content).map_err(|e| format!("配置文件格式错误: {}", e))?;
+        let content = fs::read_to_string(&config_path)
+            .map_err(|e| AppError::Settings(format!("Failed to read config file: {}", e)))?;
+        let mut settings: Self = match serde_json::from_str(&content) {
+            Ok(settings) => settings,
+            Err(e) => {
+                // 损坏的配置文件备份后用默认配置覆盖，否则每次启动都会生成新的 RPC 密钥
+                let backup_path = config_path.with_extension("json.bak");
+                eprintln!(
+                    "Malformed config file ({}), backing it up to {}",
+                    e,
+                    backup_path.display()
+                );
+                fs::rename(&config_path, &backup_path).map_err(|e| {
+                    AppError::Settings(format!("Failed to back up config file: {}", e))
+                })?;
+                let default_settings = Self::default();
+                default_settings.save()?;
+                return Ok(default_settings);
+            }
+        };
+
+        // 旧版本的配置文件没有密钥，生成后立即保存，保证之后每次启动使用同一个密钥
+        if settings.rpc_secret.is_empty() {
+            settings.rpc_secret = generate_rpc_secret();
+            settings.save()?;
+        }
 
         Ok(settings)
     }
 
     /// 保存配置到文件
-    pub fn save(&self) -> Result<(), String> {
+    pub fn save(&self) -> Result<(), AppError> {
         let config_path = Self::get_config_path()?;
 
-        let content =
-            serde_json::to_string_pretty(self).map_err(|e| format!("序列化配置失败: {}", e))?;
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::Settings(format!("Failed to serialize config: {}", e)))?;
 
-        fs::write(&config_path, content).map_err(|e| format!("保存配置文件失败: {}", e))?;
+        fs::write(&config_path, content)
+            .map_err(|e| AppError::Settings(format!("Failed to write config file: {}", e)))?;
 
         Ok(())
     }
@@ -137,22 +345,85 @@ impl DownloadSettings {
         }
     }
 
-    /// 为 aria2c 生成选项
+    /// 更新 aria2c 进程相关设置，端口在下次启动 aria2c 时生效
+    pub fn update_daemon_settings(
+        &mut self,
+        rpc_port: Option<u16>,
+        shutdown_timeout_secs: Option<u64>,
+    ) {
+        if let Some(port) = rpc_port {
+            self.rpc_port = port;
+        }
+        if let Some(timeout) = shutdown_timeout_secs {
+            self.shutdown_timeout_secs = timeout;
+        }
+    }
+
+    /// 关闭 aria2c 的等待时间
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+
+    /// 新任务所属的分类：优先使用手动指定的分类，否则取第一个规则匹配的分类
+    pub fn category_for(
+        &self,
+        task_settings: Option<&NewTaskSettings>,
+        task: &TaskDescriptor,
+    ) -> Result<Option<&Category>, AppError> {
+        match task_settings.and_then(|ts| ts.category.as_deref()) {
+            Some(name) => self
+                .categories
+                .iter()
+                .find(|c| c.name == name)
+                .map(Some)
+                .ok_or_else(|| AppError::InvalidInput(format!("Unknown category: {}", name))),
+            None => Ok(self.categories.iter().find(|c| c.matches(task))),
+        }
+    }
+
+    /// 只指定了下载目录的任务使用的分类选项：分类只决定默认目录和限制，指定的目录优先；
+    /// 没有匹配的分类也没有指定目录时为 `None`
+    pub fn category_options_with_dir(
+        &self,
+        task: &TaskDescriptor,
+        dir: Option<String>,
+    ) -> Option<HashMap<String, String>> {
+        let mut options = self
+            .categories
+            .iter()
+            .find(|c| c.matches(task))
+            .map(Category::to_aria2c_options)
+            .unwrap_or_default();
+        if let Some(dir) = dir {
+            options.insert("dir".to_string(), dir);
+        }
+        if options.is_empty() {
+            None
+        } else {
+            Some(options)
+        }
+    }
+
+    /// 为 aria2c 生成选项，优先级：任务设置 > 分类 > 全局设置
     pub fn to_aria2c_options(
         &self,
         task_settings: Option<&NewTaskSettings>,
-    ) -> HashMap<String, String> {
+        task: &TaskDescriptor,
+    ) -> Result<HashMap<String, String>, AppError> {
         let mut options = HashMap::new();
+        let category = self.category_for(task_settings, task)?;
 
         // 设置下载目录
         let download_dir = task_settings
             .and_then(|ts| ts.download_dir.as_ref())
+            .or(category.map(|c| &c.dir))
             .unwrap_or(&self.default_download_dir);
         options.insert("dir".to_string(), download_dir.clone());
 
         // 设置速度限制
         let max_download_speed = task_settings
             .and_then(|ts| ts.max_download_speed)
+            .or(category.and_then(|c| c.max_download_speed))
             .unwrap_or(self.max_download_speed);
         if max_download_speed > 0 {
             options.insert(
@@ -163,6 +434,7 @@ impl DownloadSettings {
 
         let max_upload_speed = task_settings
             .and_then(|ts| ts.max_upload_speed)
+            .or(category.and_then(|c| c.max_upload_speed))
             .unwrap_or(self.max_upload_speed);
         if max_upload_speed > 0 {
             options.insert("max-upload-limit".to_string(), max_upload_speed.to_string());
@@ -171,6 +443,7 @@ impl DownloadSettings {
         // 设置连接数
         let max_connections = task_settings
             .and_then(|ts| ts.max_connections)
+            .or(category.and_then(|c| c.max_connections))
             .unwrap_or(self.max_connections_per_task);
         options.insert(
             "max-connection-per-server".to_string(),
@@ -181,24 +454,47 @@ impl DownloadSettings {
         // 自动文件重命名
         options.insert("auto-file-renaming".to_string(), "true".to_string());
 
-        options
+        // 文件摘要校验
+        if let Some(checksum) = task_settings.and_then(|ts| ts.checksum.as_ref()) {
+            options.insert("checksum".to_string(), checksum.to_aria2c_option());
+        }
+
+        Ok(options)
+    }
+
+    /// 指定时间生效的全局限速：限速计划中匹配的方案，没有则使用全局设置
+    pub fn speed_limits_at(&self, at: NaiveDateTime) -> SpeedLimits {
+        match self.speed_schedule.active_profile(at) {
+            Some(profile) => SpeedLimits {
+                profile: Some(profile.name.clone()),
+                max_download_speed: profile.max_download_speed,
+                max_upload_speed: profile.max_upload_speed,
+            },
+            None => SpeedLimits {
+                profile: None,
+                max_download_speed: self.max_download_speed,
+                max_upload_speed: self.max_upload_speed,
+            },
+        }
     }
 
     /// 获取全局 aria2c 配置
     pub fn get_global_aria2c_config(&self) -> HashMap<String, String> {
         let mut config = HashMap::new();
 
-        if self.max_download_speed > 0 {
+        // 限速计划启用时使用当前时间段的限速
+        let limits = self.speed_limits_at(chrono::Local::now().naive_local());
+        if limits.max_download_speed > 0 {
             config.insert(
                 "max-overall-download-limit".to_string(),
-                self.max_download_speed.to_string(),
+                limits.max_download_speed.to_string(),
             );
         }
 
-        if self.max_upload_speed > 0 {
+        if limits.max_upload_speed > 0 {
             config.insert(
                 "max-overall-upload-limit".to_string(),
-                self.max_upload_speed.to_string(),
+                limits.max_upload_speed.to_string(),
             );
         }
 
@@ -210,3 +506,113 @@ impl DownloadSettings {
         config
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::category::CategoryRule;
+
+    fn settings() -> DownloadSettings {
+        DownloadSettings {
+            default_download_dir: "/downloads".to_string(),
+            max_download_speed: 0,
+            max_upload_speed: 2048,
+            max_connections_per_task: 16,
+            categories: vec![Category {
+                name: "video".to_string(),
+                dir: "/downloads/video".to_string(),
+                rules: vec![CategoryRule::Extension {
+                    extensions: vec!["mkv".to_string()],
+                }],
+                max_download_speed: Some(1024),
+                max_upload_speed: None,
+                max_connections: Some(4),
+            }],
+            ..DownloadSettings::default()
+        }
+    }
+
+    fn task(uri: &str) -> TaskDescriptor {
+        TaskDescriptor::from_uris(&[uri.to_string()])
+    }
+
+    fn task_settings() -> NewTaskSettings {
+        NewTaskSettings {
+            download_dir: None,
+            max_download_speed: None,
+            max_upload_speed: None,
+            max_connections: None,
+            checksum: None,
+            category: None,
+        }
+    }
+
+    #[test]
+    fn uses_global_settings_without_a_category() {
+        let options = settings()
+            .to_aria2c_options(None, &task("https://example.com/a.iso"))
+            .unwrap();
+        assert_eq!(options["dir"], "/downloads");
+        assert_eq!(options["max-connection-per-server"], "16");
+        assert_eq!(options["max-upload-limit"], "2048");
+        // 全局不限速时不设置
+        assert!(!options.contains_key("max-download-limit"));
+        assert!(!options.contains_key("checksum"));
+    }
+
+    #[test]
+    fn category_overrides_global_settings() {
+        let options = settings()
+            .to_aria2c_options(None, &task("https://example.com/a.mkv"))
+            .unwrap();
+        assert_eq!(options["dir"], "/downloads/video");
+        assert_eq!(options["max-download-limit"], "1024");
+        assert_eq!(options["max-upload-limit"], "2048");
+        assert_eq!(options["split"], "4");
+    }
+
+    #[test]
+    fn task_settings_override_the_category() {
+        let mut overrides = task_settings();
+        overrides.download_dir = Some("/elsewhere".to_string());
+        overrides.max_download_speed = Some(512);
+        overrides.checksum =
+            ExpectedChecksum::from_aria2c_option(&format!("md5={}", "0".repeat(32)));
+        let options = settings()
+            .to_aria2c_options(Some(&overrides), &task("https://example.com/a.mkv"))
+            .unwrap();
+        assert_eq!(options["dir"], "/elsewhere");
+        assert_eq!(options["max-download-limit"], "512");
+        assert_eq!(options["max-connection-per-server"], "4");
+        assert_eq!(options["checksum"], format!("md5={}", "0".repeat(32)));
+    }
+
+    #[test]
+    fn named_category_replaces_rule_matching() {
+        let mut named = task_settings();
+        named.category = Some("video".to_string());
+        let options = settings()
+            .to_aria2c_options(Some(&named), &task("https://example.com/a.iso"))
+            .unwrap();
+        assert_eq!(options["dir"], "/downloads/video");
+
+        named.category = Some("music".to_string());
+        assert!(matches!(
+            settings().to_aria2c_options(Some(&named), &task("https://example.com/a.iso")),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn explicit_dir_wins_over_the_category_dir() {
+        let settings = settings();
+        let options = settings
+            .category_options_with_dir(&task("https://example.com/a.mkv"), Some("/tmp".to_string()))
+            .unwrap();
+        assert_eq!(options["dir"], "/tmp");
+        assert_eq!(options["max-download-limit"], "1024");
+        assert!(settings
+            .category_options_with_dir(&task("https://example.com/a.iso"), None)
+            .is_none());
+    }
+}
@@ -0,0 +1,132 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::sync::PoisonError;
+
+/// 应用统一错误类型
+///
+/// 所有 Tauri 命令都返回该类型，序列化后前端收到 `{kind, code, message}`，
+/// 可以根据 `kind` 区分错误类别，`code` 仅在 aria2 返回 JSON-RPC 错误时存在。
+#[derive(Debug)]
+pub enum AppError {
+    /// 无法与 aria2c 通信（连接被拒绝、超时等）
+    Transport(String),
+    /// aria2c 返回的 JSON-RPC 错误，`code` 为 aria2 的错误码
+    Rpc { code: i32, message: String },
+    /// 响应或数据解析失败
+    Parse(String),
+    /// 设置文件读写失败
+    Settings(String),
+    /// 共享状态的锁已被污染
+    LockPoisoned(String),
+    /// 调用方传入的参数无效
+    InvalidInput(String),
+    /// 文件读写失败
+    Io(String),
+    /// aria2c 进程启动或停止失败
+    Process(String),
+    /// 本地数据库（下载历史）读写失败
+    Database(String),
+}
+
+impl AppError {
+    /// 错误类别，对应序列化结果中的 `kind`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Transport(_) => "transport",
+            AppError::Rpc { .. } => "rpc",
+            AppError::Parse(_) => "parse",
+            AppError::Settings(_) => "settings",
+            AppError::LockPoisoned(_) => "lock_poisoned",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::Io(_) => "io",
+            AppError::Process(_) => "process",
+            AppError::Database(_) => "database",
+        }
+    }
+
+    /// aria2 的 JSON-RPC 错误码
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            AppError::Rpc { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Transport(message)
+            | AppError::Rpc { message, .. }
+            | AppError::Parse(message)
+            | AppError::Settings(message)
+            | AppError::LockPoisoned(message)
+            | AppError::InvalidInput(message)
+            | AppError::Io(message)
+            | AppError::Process(message)
+            | AppError::Database(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code() {
+            Some(code) => write!(
+                f,
+                "{} error (code {}): {}",
+                self.kind(),
+                code,
+                self.message()
+            ),
+            None => write!(f, "{} error: {}", self.kind(), self.message()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::Transport(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Parse(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+impl From<base64::DecodeError> for AppError {
+    fn from(e: base64::DecodeError) -> Self {
+        AppError::InvalidInput(format!("Invalid base64 data: {}", e))
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Database(e.to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for AppError {
+    fn from(e: PoisonError<T>) -> Self {
+        AppError::LockPoisoned(e.to_string())
+    }
+}
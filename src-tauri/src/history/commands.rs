@@ -0,0 +1,48 @@
+use crate::error::AppError;
+use crate::history::store::{HistoryPage, HistoryStore};
+
+/// 单页最多返回的记录数
+const MAX_PAGE_SIZE: u64 = 500;
+
+fn page_limit(limit: Option<u64>) -> u64 {
+    limit.unwrap_or(50).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// 分页获取下载历史，可按状态 (complete/error/removed/...) 过滤
+#[tauri::command]
+pub async fn get_download_history(
+    offset: Option<u64>,
+    limit: Option<u64>,
+    status: Option<String>,
+    history_state: tauri::State<'_, HistoryStore>,
+) -> Result<HistoryPage, AppError> {
+    history_state.query(status.as_deref(), offset.unwrap_or(0), page_limit(limit))
+}
+
+/// 按名称、来源地址、info-hash 或保存目录搜索下载历史
+#[tauri::command]
+pub async fn search_download_history(
+    keyword: String,
+    offset: Option<u64>,
+    limit: Option<u64>,
+    history_state: tauri::State<'_, HistoryStore>,
+) -> Result<HistoryPage, AppError> {
+    history_state.search(keyword.trim(), offset.unwrap_or(0), page_limit(limit))
+}
+
+/// 删除指定的历史记录，返回删除的条数
+#[tauri::command]
+pub async fn delete_download_history(
+    ids: Vec<i64>,
+    history_state: tauri::State<'_, HistoryStore>,
+) -> Result<usize, AppError> {
+    history_state.delete(&ids)
+}
+
+/// 清空下载历史，返回删除的条数
+#[tauri::command]
+pub async fn clear_download_history(
+    history_state: tauri::State<'_, HistoryStore>,
+) -> Result<usize, AppError> {
+    history_state.clear()
+}
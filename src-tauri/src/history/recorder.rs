@@ -0,0 +1,158 @@
+use crate::aria2c::{Aria2cEvents, Aria2cState, DownloadEvent, DownloadEventKind};
+use crate::error::AppError;
+use crate::history::store::{HistoryFinish, HistoryStart, HistoryStore};
+use serde_json::Value;
+use tokio::sync::broadcast::error::RecvError;
+
+/// 记录历史需要的 tellStatus 字段
+const STATUS_KEYS: [&str; 11] = [
+    "gid",
+    "status",
+    "totalLength",
+    "completedLength",
+    "dir",
+    "files",
+    "infoHash",
+    "bittorrent",
+    "errorCode",
+    "errorMessage",
+    "followedBy",
+];
+
+/// 订阅 aria2c 的下载通知，把任务的开始、暂停和结束写入历史数据库
+pub fn start_history_recorder(
+    store: HistoryStore,
+    aria2c_state: Aria2cState,
+    events: Aria2cEvents,
+) {
+    let mut receiver = events.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if let Err(e) = record_event(&store, &aria2c_state, &event).await {
+                        eprintln!("Failed to record history for {}: {}", event.gid, e);
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    eprintln!("History recorder lagged, {} events skipped", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+async fn record_event(
+    store: &HistoryStore,
+    aria2c_state: &Aria2cState,
+    event: &DownloadEvent,
+) -> Result<(), AppError> {
+    let client = aria2c_state.client()?;
+    let keys = STATUS_KEYS.iter().map(|key| key.to_string()).collect();
+
+    match event.kind {
+        DownloadEventKind::Start => {
+            // 只获取元数据的任务不是用户的下载，不记录
+            let options = client.get_option(&event.gid).await?;
+            if options.get("bt-metadata-only").map(String::as_str) == Some("true") {
+                return Ok(());
+            }
+            let status = client.tell_status(&event.gid, Some(keys)).await?;
+            store.record_start(&HistoryStart {
+                gid: event.gid.clone(),
+                name: task_name(&status).unwrap_or_else(|| event.gid.clone()),
+                source_uris: source_uris(&status),
+                info_hash: str_field(&status, "infoHash"),
+                dir: str_field(&status, "dir"),
+                total_length: u64_field(&status, "totalLength").unwrap_or(0),
+            })
+        }
+        _ => {
+            let finish = match client.tell_status(&event.gid, Some(keys)).await {
+                // 磁力链接的元数据任务完成后由真正的下载任务接替，只保留后者的记录
+                Ok(status)
+                    if status
+                        .get("followedBy")
+                        .and_then(|v| v.as_array())
+                        .is_some_and(|gids| !gids.is_empty()) =>
+                {
+                    return store.forget(&event.gid);
+                }
+                Ok(status) => HistoryFinish {
+                    gid: event.gid.clone(),
+                    status: str_field(&status, "status").unwrap_or_else(|| "removed".to_string()),
+                    total_length: u64_field(&status, "totalLength"),
+                    completed_length: u64_field(&status, "completedLength"),
+                    finished: event.kind != DownloadEventKind::Pause,
+                    error_code: str_field(&status, "errorCode").filter(|code| code != "0"),
+                    error_message: str_field(&status, "errorMessage")
+                        .filter(|message| !message.is_empty()),
+                },
+                // 任务被删除后 aria2 不再保留状态，只记录删除
+                Err(AppError::Rpc { .. }) => HistoryFinish {
+                    gid: event.gid.clone(),
+                    status: "removed".to_string(),
+                    total_length: None,
+                    completed_length: None,
+                    finished: true,
+                    error_code: None,
+                    error_message: None,
+                },
+                Err(e) => return Err(e),
+            };
+            store.record_finish(&finish)
+        }
+    }
+}
+
+fn str_field(status: &Value, key: &str) -> Option<String> {
+    status.get(key)?.as_str().map(|s| s.to_string())
+}
+
+fn u64_field(status: &Value, key: &str) -> Option<u64> {
+    status.get(key)?.as_str()?.parse().ok()
+}
+
+/// 任务名称：BT 任务取种子名，否则取第一个文件名或第一个地址
+fn task_name(status: &Value) -> Option<String> {
+    if let Some(name) = status
+        .pointer("/bittorrent/info/name")
+        .and_then(|v| v.as_str())
+        .filter(|name| !name.is_empty())
+    {
+        return Some(name.to_string());
+    }
+
+    let file = status.pointer("/files/0")?;
+    let path = file.get("path").and_then(|v| v.as_str()).unwrap_or("");
+    if !path.is_empty() {
+        return std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+    }
+    file.pointer("/uris/0/uri")
+        .and_then(|v| v.as_str())
+        .map(|uri| uri.to_string())
+}
+
+/// 来源地址：BT 任务根据 info-hash 生成磁力链接，其余任务取所有文件的去重地址
+fn source_uris(status: &Value) -> Vec<String> {
+    if let Some(info_hash) = status.get("infoHash").and_then(|v| v.as_str()) {
+        return vec![format!("magnet:?xt=urn:btih:{}", info_hash)];
+    }
+
+    let mut uris: Vec<String> = Vec::new();
+    let files = status.get("files").and_then(|v| v.as_array());
+    for file in files.into_iter().flatten() {
+        let file_uris = file.get("uris").and_then(|v| v.as_array());
+        for uri in file_uris.into_iter().flatten() {
+            if let Some(uri) = uri.get("uri").and_then(|v| v.as_str()) {
+                if !uris.iter().any(|u| u == uri) {
+                    uris.push(uri.to_string());
+                }
+            }
+        }
+    }
+    uris
+}
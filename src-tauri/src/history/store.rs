@@ -0,0 +1,345 @@
+use crate::error::AppError;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// 一条下载历史记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub gid: String,
+    pub name: String,
+    /// 来源地址（HTTP/FTP 链接，BT 任务为根据 info-hash 生成的磁力链接）
+    pub source_uris: Vec<String>,
+    pub info_hash: Option<String>,
+    /// 保存目录
+    pub dir: Option<String>,
+    pub total_length: u64,
+    pub completed_length: u64,
+    /// active/paused/complete/error/removed
+    pub status: String,
+    /// 开始时间 (Unix 秒)
+    pub started_at: i64,
+    /// 结束时间 (Unix 秒)
+    pub finished_at: Option<i64>,
+    /// 平均速度 (bytes/s)，按下载中的时间计算，不含暂停和排队的时间
+    pub average_speed: Option<u64>,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+}
+
+/// 一页查询结果
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub total: u64,
+}
+
+/// 任务开始时记录的信息
+#[derive(Debug, Clone)]
+pub struct HistoryStart {
+    pub gid: String,
+    pub name: String,
+    pub source_uris: Vec<String>,
+    pub info_hash: Option<String>,
+    pub dir: Option<String>,
+    pub total_length: u64,
+}
+
+/// 任务结束（或暂停）时更新的信息
+#[derive(Debug, Clone)]
+pub struct HistoryFinish {
+    pub gid: String,
+    pub status: String,
+    pub total_length: Option<u64>,
+    pub completed_length: Option<u64>,
+    pub finished: bool,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+}
+
+/// 早期版本的表中没有的列，打开数据库时补上
+const ADDED_COLUMNS: [(&str, &str); 2] = [
+    ("active_seconds", "INTEGER NOT NULL DEFAULT 0"),
+    ("resumed_at", "INTEGER"),
+];
+
+/// 本程序校验摘要失败时写入的错误码，区别于 aria2 的数字错误码
+pub const CHECKSUM_MISMATCH_CODE: &str = "checksum_mismatch";
+
+const SELECT_COLUMNS: &str = "id, gid, name, source_uris, info_hash, dir, total_length, \
+     completed_length, status, started_at, finished_at, average_speed, error_code, error_message";
+
+/// 基于 SQLite 的下载历史，独立于 aria2 的已停止列表
+#[derive(Clone)]
+pub struct HistoryStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS download_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                gid TEXT NOT NULL UNIQUE,
+                name TEXT NOT NULL,
+                source_uris TEXT NOT NULL,
+                info_hash TEXT,
+                dir TEXT,
+                total_length INTEGER NOT NULL DEFAULT 0,
+                completed_length INTEGER NOT NULL DEFAULT 0,
+                status TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                finished_at INTEGER,
+                average_speed INTEGER,
+                error_code TEXT,
+                error_message TEXT,
+                active_seconds INTEGER NOT NULL DEFAULT 0,
+                resumed_at INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_download_history_started_at
+                ON download_history (started_at);",
+        )?;
+        let columns = conn
+            .prepare("SELECT name FROM pragma_table_info('download_history')")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (name, definition) in ADDED_COLUMNS {
+            if !columns.iter().any(|column| column == name) {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE download_history ADD COLUMN {} {}",
+                    name, definition
+                ))?;
+            }
+        }
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// 记录任务开始或恢复；aria2 从会话恢复的任务会沿用原 GID，此时只更新状态
+    pub fn record_start(&self, start: &HistoryStart) -> Result<(), AppError> {
+        let conn = self.conn.lock()?;
+        conn.execute(
+            "INSERT INTO download_history
+                (gid, name, source_uris, info_hash, dir, total_length, status, started_at,
+                 resumed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'active', ?7, ?7)
+             ON CONFLICT(gid) DO UPDATE SET
+                name = excluded.name,
+                dir = excluded.dir,
+                total_length = MAX(total_length, excluded.total_length),
+                status = 'active',
+                finished_at = NULL,
+                resumed_at = COALESCE(resumed_at, excluded.resumed_at)",
+            params![
+                start.gid,
+                start.name,
+                serde_json::to_string(&start.source_uris)?,
+                start.info_hash,
+                start.dir,
+                start.total_length as i64,
+                chrono::Utc::now().timestamp(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 记录任务结束或暂停，累计下载中的时间，结束时据此计算平均速度
+    pub fn record_finish(&self, finish: &HistoryFinish) -> Result<(), AppError> {
+        let conn = self.conn.lock()?;
+        let times: Option<(i64, Option<i64>)> = conn
+            .query_row(
+                "SELECT active_seconds, resumed_at FROM download_history WHERE gid = ?1",
+                params![finish.gid],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((active_seconds, resumed_at)) = times else {
+            // 没有开始记录（例如在本程序启动前就已结束），无需更新
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let active_seconds = active_seconds + resumed_at.map_or(0, |at| (now - at).max(0));
+        let finished_at = finish.finished.then_some(now);
+        let average_speed = match (finish.finished, finish.completed_length) {
+            (true, Some(completed)) => Some(completed / active_seconds.max(1) as u64),
+            _ => None,
+        };
+
+        conn.execute(
+            "UPDATE download_history SET
+                status = ?2,
+                total_length = COALESCE(?3, total_length),
+                completed_length = COALESCE(?4, completed_length),
+                finished_at = COALESCE(?5, finished_at),
+                average_speed = COALESCE(?6, average_speed),
+                error_code = ?7,
+                error_message = ?8,
+                active_seconds = ?10,
+                resumed_at = NULL
+             WHERE gid = ?1 AND error_code IS NOT ?9",
+            params![
+                finish.gid,
+                finish.status,
+                finish.total_length.map(|v| v as i64),
+                finish.completed_length.map(|v| v as i64),
+                finished_at,
+                average_speed.map(|v| v as i64),
+                finish.error_code,
+                finish.error_message,
+                // 校验失败的结论不会被之后到达的完成通知覆盖
+                CHECKSUM_MISMATCH_CODE,
+                active_seconds,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 删除某个任务的记录，用于被后续任务接替的磁力链接元数据任务
+    pub fn forget(&self, gid: &str) -> Result<(), AppError> {
+        let conn = self.conn.lock()?;
+        conn.execute("DELETE FROM download_history WHERE gid = ?1", params![gid])?;
+        Ok(())
+    }
+
+    /// 记录摘要校验失败，任务标记为失败并保存期望值和实际值
+    pub fn record_checksum_mismatch(
+        &self,
+        gid: &str,
+        algorithm: &str,
+        expected: &str,
+        actual: &str,
+    ) -> Result<(), AppError> {
+        let conn = self.conn.lock()?;
+        conn.execute(
+            "UPDATE download_history SET
+                status = 'error',
+                finished_at = COALESCE(finished_at, ?2),
+                error_code = ?3,
+                error_message = ?4
+             WHERE gid = ?1",
+            params![
+                gid,
+                chrono::Utc::now().timestamp(),
+                CHECKSUM_MISMATCH_CODE,
+                format!(
+                    "Checksum mismatch ({}): expected {}, actual {}",
+                    algorithm, expected, actual
+                ),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 按状态分页查询，按开始时间倒序
+    pub fn query(
+        &self,
+        status: Option<&str>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<HistoryPage, AppError> {
+        match status {
+            Some(status) => self.select_page("status = ?", vec![status.to_string()], offset, limit),
+            None => self.select_page("1 = 1", vec![], offset, limit),
+        }
+    }
+
+    /// 按名称、来源地址或 info-hash 搜索
+    pub fn search(&self, keyword: &str, offset: u64, limit: u64) -> Result<HistoryPage, AppError> {
+        let pattern = format!(
+            "%{}%",
+            keyword
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        self.select_page(
+            "(name LIKE ? ESCAPE '\\' OR source_uris LIKE ? ESCAPE '\\' \
+             OR info_hash LIKE ? ESCAPE '\\' OR dir LIKE ? ESCAPE '\\')",
+            vec![pattern.clone(), pattern.clone(), pattern.clone(), pattern],
+            offset,
+            limit,
+        )
+    }
+
+    /// 删除指定记录，返回删除的条数
+    pub fn delete(&self, ids: &[i64]) -> Result<usize, AppError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let conn = self.conn.lock()?;
+        let deleted = conn.execute(
+            &format!(
+                "DELETE FROM download_history WHERE id IN ({})",
+                placeholders
+            ),
+            params_from_iter(ids.iter()),
+        )?;
+        Ok(deleted)
+    }
+
+    /// 清空全部历史，返回删除的条数
+    pub fn clear(&self) -> Result<usize, AppError> {
+        let conn = self.conn.lock()?;
+        Ok(conn.execute("DELETE FROM download_history", [])?)
+    }
+
+    fn select_page(
+        &self,
+        condition: &str,
+        args: Vec<String>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<HistoryPage, AppError> {
+        let conn = self.conn.lock()?;
+
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM download_history WHERE {}", condition),
+            params_from_iter(args.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM download_history WHERE {} \
+             ORDER BY started_at DESC, id DESC LIMIT {} OFFSET {}",
+            SELECT_COLUMNS, condition, limit, offset
+        ))?;
+        let entries = stmt
+            .query_map(params_from_iter(args.iter()), Self::map_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(HistoryPage {
+            entries,
+            total: total as u64,
+        })
+    }
+
+    fn map_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+        let source_uris: String = row.get(3)?;
+        Ok(HistoryEntry {
+            id: row.get(0)?,
+            gid: row.get(1)?,
+            name: row.get(2)?,
+            source_uris: serde_json::from_str(&source_uris).unwrap_or_default(),
+            info_hash: row.get(4)?,
+            dir: row.get(5)?,
+            total_length: row.get::<_, i64>(6)? as u64,
+            completed_length: row.get::<_, i64>(7)? as u64,
+            status: row.get(8)?,
+            started_at: row.get(9)?,
+            finished_at: row.get(10)?,
+            average_speed: row.get::<_, Option<i64>>(11)?.map(|v| v as u64),
+            error_code: row.get(12)?,
+            error_message: row.get(13)?,
+        })
+    }
+}
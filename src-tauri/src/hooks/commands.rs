@@ -0,0 +1,50 @@
+use crate::config::settings::DownloadSettings;
+use crate::error::AppError;
+use crate::hooks::config::CompletionHooks;
+use crate::hooks::log::log_path;
+use crate::hooks::runner::CompletionHookRunner;
+use std::sync::{Arc, Mutex};
+
+/// 获取任务完成后的处理设置
+#[tauri::command]
+pub async fn get_completion_hooks(
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<CompletionHooks, AppError> {
+    Ok(settings_state.lock()?.completion_hooks.clone())
+}
+
+/// 更新任务完成后的处理设置
+#[tauri::command]
+pub async fn update_completion_hooks(
+    hooks: CompletionHooks,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<CompletionHooks, AppError> {
+    hooks.validate().map_err(AppError::InvalidInput)?;
+
+    let mut settings = settings_state.lock()?;
+    settings.completion_hooks = hooks.clone();
+    settings.save()?;
+
+    Ok(hooks)
+}
+
+/// 读取任务的完成处理日志，没有日志时返回空字符串
+#[tauri::command]
+pub async fn get_hook_log(
+    gid: String,
+    runner: tauri::State<'_, CompletionHookRunner>,
+) -> Result<String, AppError> {
+    match tokio::fs::read_to_string(log_path(runner.log_dir(), &gid)).await {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 取消等待中的关机或睡眠
+#[tauri::command]
+pub async fn cancel_power_action(
+    runner: tauri::State<'_, CompletionHookRunner>,
+) -> Result<bool, AppError> {
+    Ok(runner.cancel_power_action())
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+/// 任务完成后的处理
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompletionHooks {
+    /// 每个任务完成后按顺序执行的动作
+    #[serde(default)]
+    pub actions: Vec<CompletionAction>,
+    /// 全部任务完成、队列为空时执行的电源操作
+    #[serde(default)]
+    pub when_queue_empty: Option<PowerAction>,
+}
+
+/// 任务完成后的单个动作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CompletionAction {
+    /// 把下载的文件移动到分类目录
+    #[serde(rename_all = "camelCase")]
+    MoveTo {
+        dir: String,
+        /// 只移动包含这些扩展名的任务 (不带点，不区分大小写)，为空表示全部
+        #[serde(default)]
+        extensions: Vec<String>,
+    },
+    /// 解压 zip/7z/tar/tar.gz 压缩包
+    #[serde(rename_all = "camelCase")]
+    Extract {
+        /// 解压目录，为空时解压到压缩包旁边与压缩包同名的目录
+        #[serde(default)]
+        dir: Option<String>,
+        /// 解压成功后删除压缩包
+        #[serde(default)]
+        delete_archive: bool,
+    },
+    /// 运行用户脚本
+    ///
+    /// 参数中的 `{gid}`、`{path}`、`{name}`、`{dir}` 会被替换，未指定参数时依次传入 GID、路径和名称
+    #[serde(rename_all = "camelCase")]
+    RunCommand {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// 队列为空时的电源操作
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PowerAction {
+    Shutdown,
+    Sleep,
+}
+
+impl CompletionAction {
+    /// 动作名称，用于日志
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompletionAction::MoveTo { .. } => "move",
+            CompletionAction::Extract { .. } => "extract",
+            CompletionAction::RunCommand { .. } => "command",
+        }
+    }
+}
+
+impl CompletionHooks {
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty() && self.when_queue_empty.is_none()
+    }
+
+    /// 校验配置，返回第一个错误
+    pub fn validate(&self) -> Result<(), String> {
+        for action in &self.actions {
+            match action {
+                CompletionAction::MoveTo { dir, .. } if dir.trim().is_empty() => {
+                    return Err("Move action requires a target directory".to_string());
+                }
+                CompletionAction::RunCommand { program, .. } if program.trim().is_empty() => {
+                    return Err("Command action requires a program".to_string());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use crate::error::AppError;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Component, Path, PathBuf};
+
+/// 支持的压缩包格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Zip,
+    SevenZ,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// 根据文件名判断格式
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".7z") {
+            Some(ArchiveKind::SevenZ)
+        } else {
+            None
+        }
+    }
+}
+
+/// 默认的解压目录：压缩包旁边去掉扩展名的同名目录
+pub fn default_extract_dir(archive: &Path) -> PathBuf {
+    let name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let lower = name.to_ascii_lowercase();
+    let stem_len = [".tar.gz", ".tgz", ".tar", ".zip", ".7z"]
+        .iter()
+        .find(|ext| lower.ends_with(*ext))
+        .map_or(name.len(), |ext| name.len() - ext.len());
+    archive.with_file_name(&name[..stem_len])
+}
+
+/// 解压到指定目录，拒绝解压到目录之外的条目 (zip slip)
+///
+/// 读取和写入都是阻塞操作，需要在 `spawn_blocking` 中调用
+pub fn extract_archive(archive: &Path, kind: ArchiveKind, dest: &Path) -> Result<(), AppError> {
+    fs::create_dir_all(dest)?;
+    match kind {
+        ArchiveKind::Zip => {
+            let file = BufReader::new(File::open(archive)?);
+            let mut zip = zip::ZipArchive::new(file).map_err(|e| archive_error(archive, e))?;
+            // extract 会拒绝解压到目录之外的条目
+            zip.extract(dest).map_err(|e| archive_error(archive, e))
+        }
+        ArchiveKind::SevenZ => {
+            sevenz_rust::decompress_file_with_extract_fn(archive, dest, |entry, reader, path| {
+                if !is_safe_entry(entry.name()) {
+                    eprintln!("Skipping unsafe archive entry: {}", entry.name());
+                    return Ok(true);
+                }
+                sevenz_rust::default_entry_extract_fn(entry, reader, path)
+            })
+            .map_err(|e| archive_error(archive, e))
+        }
+        ArchiveKind::Tar => {
+            let file = BufReader::new(File::open(archive)?);
+            // unpack 会拒绝包含 ".." 的条目
+            tar::Archive::new(file)
+                .unpack(dest)
+                .map_err(|e| archive_error(archive, e))
+        }
+        ArchiveKind::TarGz => {
+            let file = BufReader::new(File::open(archive)?);
+            tar::Archive::new(flate2::read::GzDecoder::new(file))
+                .unpack(dest)
+                .map_err(|e| archive_error(archive, e))
+        }
+    }
+}
+
+/// 条目路径只能是普通的相对路径
+fn is_safe_entry(name: &str) -> bool {
+    Path::new(name)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+fn archive_error(archive: &Path, e: impl std::fmt::Display) -> AppError {
+    AppError::Io(format!("Failed to extract {}: {}", archive.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_archive_kinds() {
+        assert_eq!(
+            ArchiveKind::detect(Path::new("/d/a.TAR.GZ")),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(
+            ArchiveKind::detect(Path::new("a.tgz")),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(
+            ArchiveKind::detect(Path::new("a.tar")),
+            Some(ArchiveKind::Tar)
+        );
+        assert_eq!(
+            ArchiveKind::detect(Path::new("a.zip")),
+            Some(ArchiveKind::Zip)
+        );
+        assert_eq!(
+            ArchiveKind::detect(Path::new("a.7z")),
+            Some(ArchiveKind::SevenZ)
+        );
+        assert_eq!(ArchiveKind::detect(Path::new("a.gz")), None);
+        assert_eq!(ArchiveKind::detect(Path::new("/")), None);
+    }
+
+    #[test]
+    fn extracts_next_to_the_archive() {
+        assert_eq!(
+            default_extract_dir(Path::new("/d/Game.Tar.Gz")),
+            PathBuf::from("/d/Game")
+        );
+        assert_eq!(
+            default_extract_dir(Path::new("/d/v1.2.zip")),
+            PathBuf::from("/d/v1.2")
+        );
+        assert_eq!(
+            default_extract_dir(Path::new("/d/a.tgz")),
+            PathBuf::from("/d/a")
+        );
+    }
+
+    #[test]
+    fn rejects_entries_outside_the_destination() {
+        assert!(is_safe_entry("dir/file.txt"));
+        assert!(is_safe_entry("./file.txt"));
+        assert!(is_safe_entry("..file"));
+        assert!(!is_safe_entry("../file.txt"));
+        assert!(!is_safe_entry("dir/../../file.txt"));
+        assert!(!is_safe_entry("/etc/passwd"));
+    }
+}
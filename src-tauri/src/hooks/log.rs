@@ -0,0 +1,53 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 单个任务的完成处理日志，保存为 `<日志目录>/<gid>.log`
+pub struct HookLog {
+    file: Option<File>,
+}
+
+impl HookLog {
+    /// 打开（或创建）日志文件，失败时只打印警告，不影响处理
+    pub fn open(dir: &Path, gid: &str) -> Self {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!(
+                "Failed to create hook log directory {}: {}",
+                dir.display(),
+                e
+            );
+        }
+        let path = log_path(dir, gid);
+        let file = match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("Failed to open hook log {}: {}", path.display(), e);
+                None
+            }
+        };
+        Self { file }
+    }
+
+    /// 写入一行日志，`source` 标记来源 (动作名称、stdout/stderr)
+    pub fn line(&mut self, source: &str, text: &str) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let entry = format!(
+            "{} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            source,
+            text.trim_end()
+        );
+        let _ = file.write_all(entry.as_bytes());
+    }
+}
+
+/// 任务日志的路径；GID 只包含十六进制字符，其他字符替换掉以免逃出日志目录
+pub fn log_path(dir: &Path, gid: &str) -> PathBuf {
+    let name: String = gid
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir.join(format!("{}.log", name))
+}
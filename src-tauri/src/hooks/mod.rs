@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod config;
+pub mod extract;
+pub mod log;
+pub mod power;
+pub mod runner;
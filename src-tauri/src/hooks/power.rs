@@ -0,0 +1,50 @@
+use crate::error::AppError;
+use crate::hooks::config::PowerAction;
+use std::process::Command;
+
+/// 执行关机或睡眠
+pub fn execute(action: PowerAction) -> Result<(), AppError> {
+    let (program, args) = command_for(action);
+    println!(
+        "Executing power action {:?}: {} {:?}",
+        action, program, args
+    );
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| AppError::Process(format!("Failed to run {}: {}", program, e)))?;
+    if !status.success() {
+        return Err(AppError::Process(format!(
+            "{} exited with {}",
+            program, status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn command_for(action: PowerAction) -> (&'static str, &'static [&'static str]) {
+    match action {
+        PowerAction::Shutdown => ("shutdown", &["/s", "/t", "0"]),
+        PowerAction::Sleep => ("rundll32.exe", &["powrprof.dll,SetSuspendState", "0,1,0"]),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn command_for(action: PowerAction) -> (&'static str, &'static [&'static str]) {
+    match action {
+        PowerAction::Shutdown => (
+            "osascript",
+            &["-e", "tell application \"System Events\" to shut down"],
+        ),
+        PowerAction::Sleep => ("pmset", &["sleepnow"]),
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn command_for(action: PowerAction) -> (&'static str, &'static [&'static str]) {
+    match action {
+        PowerAction::Shutdown => ("systemctl", &["poweroff"]),
+        PowerAction::Sleep => ("systemctl", &["suspend"]),
+    }
+}
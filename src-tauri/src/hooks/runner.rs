@@ -0,0 +1,576 @@
+use crate::aria2c::{Aria2cEvents, Aria2cState, DownloadEvent, DownloadEventKind};
+use crate::checksum::{ChecksumOutcome, ChecksumOutcomes, ExpectedChecksum};
+use crate::config::settings::DownloadSettings;
+use crate::error::AppError;
+use crate::hooks::config::{CompletionAction, CompletionHooks, PowerAction};
+use crate::hooks::extract::{default_extract_dir, extract_archive, ArchiveKind};
+use crate::hooks::log::HookLog;
+use crate::hooks::power;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Notify;
+
+/// 用户脚本的最长运行时间
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// 电源操作执行前的等待时间，期间可以取消
+const POWER_ACTION_DELAY_SECS: u64 = 60;
+
+/// 查找压缩包时遍历目录的最大深度
+const MAX_SCAN_DEPTH: usize = 8;
+
+/// 完成处理的状态：正在处理的任务数和待执行的电源操作
+#[derive(Clone)]
+pub struct CompletionHookRunner {
+    log_dir: PathBuf,
+    running: Arc<AtomicUsize>,
+    power_pending: Arc<AtomicBool>,
+    cancel_power: Arc<Notify>,
+    checksums: ChecksumOutcomes,
+}
+
+/// 一个任务的处理结果，通过 `completion-hooks-finished` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+struct HooksFinished {
+    gid: String,
+    /// 处理后的文件位置
+    paths: Vec<String>,
+    errors: Vec<String>,
+}
+
+/// 即将执行电源操作，通过 `power-action-pending` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+struct PowerActionPending {
+    action: PowerAction,
+    delay_secs: u64,
+}
+
+/// 处理中的任务信息
+struct HookContext {
+    gid: String,
+    name: String,
+    dir: PathBuf,
+    /// 任务的顶层路径：多文件种子为种子目录，其他任务为每个选中的文件
+    entries: Vec<PathBuf>,
+}
+
+impl CompletionHookRunner {
+    pub fn new(log_dir: PathBuf, checksums: ChecksumOutcomes) -> Self {
+        Self {
+            log_dir,
+            running: Arc::new(AtomicUsize::new(0)),
+            power_pending: Arc::new(AtomicBool::new(false)),
+            cancel_power: Arc::new(Notify::new()),
+            checksums,
+        }
+    }
+
+    pub fn log_dir(&self) -> &Path {
+        &self.log_dir
+    }
+
+    /// 取消等待中的电源操作，返回是否有操作被取消
+    pub fn cancel_power_action(&self) -> bool {
+        if self.power_pending.load(Ordering::SeqCst) {
+            self.cancel_power.notify_one();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// 订阅下载通知，任务完成后按设置执行完成处理
+pub fn start_completion_hooks(
+    app_handle: tauri::AppHandle,
+    aria2c_state: Aria2cState,
+    settings_state: Arc<Mutex<DownloadSettings>>,
+    events: Aria2cEvents,
+    runner: CompletionHookRunner,
+) {
+    let mut receiver = events.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if event.kind == DownloadEventKind::Complete => {
+                    let hooks = match settings_state.lock() {
+                        Ok(settings) => settings.completion_hooks.clone(),
+                        Err(e) => {
+                            eprintln!("Failed to read completion hooks: {}", e);
+                            continue;
+                        }
+                    };
+                    if hooks.is_empty() {
+                        continue;
+                    }
+
+                    // 每个任务单独处理，解压大文件不会阻塞后续通知
+                    let app_handle = app_handle.clone();
+                    let aria2c_state = aria2c_state.clone();
+                    let runner = runner.clone();
+                    runner.running.fetch_add(1, Ordering::SeqCst);
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) =
+                            run_hooks(&app_handle, &aria2c_state, &runner, &hooks, &event).await
+                        {
+                            eprintln!("Failed to run completion hooks for {}: {}", event.gid, e);
+                        }
+                        runner.running.fetch_sub(1, Ordering::SeqCst);
+
+                        if let Some(action) = hooks.when_queue_empty {
+                            power_off_when_idle(&app_handle, &aria2c_state, &runner, action).await;
+                        }
+                    });
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    eprintln!("Completion hooks lagged, {} events skipped", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+async fn run_hooks(
+    app_handle: &tauri::AppHandle,
+    aria2c_state: &Aria2cState,
+    runner: &CompletionHookRunner,
+    hooks: &CompletionHooks,
+    event: &DownloadEvent,
+) -> Result<(), AppError> {
+    if hooks.actions.is_empty() {
+        return Ok(());
+    }
+    // 外部守护进程下载的文件不在本机
+    if aria2c_state.endpoint()?.external {
+        return Ok(());
+    }
+
+    let client = aria2c_state.client()?;
+    let options = client.get_option(&event.gid).await?;
+    if options.get("bt-metadata-only").map(String::as_str) == Some("true") {
+        return Ok(());
+    }
+    // 设置了期望摘要时等校验结束再处理文件，摘要不一致的文件不移动也不解压
+    if options
+        .get("checksum")
+        .and_then(|value| ExpectedChecksum::from_aria2c_option(value))
+        .is_some()
+    {
+        match runner.checksums.wait(&event.gid).await {
+            ChecksumOutcome::Matched | ChecksumOutcome::Skipped => {}
+            outcome => {
+                let mut log = HookLog::open(runner.log_dir(), &event.gid);
+                log.line(
+                    "hooks",
+                    &format!("Skipped, checksum verification result: {:?}", outcome),
+                );
+                return Ok(());
+            }
+        }
+    }
+    let keys = ["dir", "files", "bittorrent", "followedBy"]
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    let status = client.tell_status(&event.gid, Some(keys)).await?;
+    // 磁力链接的元数据任务完成后会由真正的下载任务接替
+    if status
+        .get("followedBy")
+        .and_then(|v| v.as_array())
+        .is_some_and(|gids| !gids.is_empty())
+    {
+        return Ok(());
+    }
+    let Some(mut context) = hook_context(&event.gid, &status) else {
+        return Ok(());
+    };
+
+    let mut log = HookLog::open(runner.log_dir(), &event.gid);
+    log.line(
+        "hooks",
+        &format!("Task {} completed: {}", context.gid, context.name),
+    );
+
+    let mut errors = Vec::new();
+    for action in &hooks.actions {
+        log.line(action.name(), "started");
+        let result = match action {
+            CompletionAction::MoveTo { dir, extensions } => {
+                move_entries(&mut context, Path::new(dir), extensions, &mut log).await
+            }
+            CompletionAction::Extract {
+                dir,
+                delete_archive,
+            } => extract_entries(&context, dir.as_deref(), *delete_archive, &mut log).await,
+            CompletionAction::RunCommand { program, args } => {
+                run_command(&context, program, args, &mut log).await
+            }
+        };
+        match result {
+            Ok(()) => log.line(action.name(), "finished"),
+            Err(e) => {
+                log.line(action.name(), &format!("failed: {}", e));
+                errors.push(format!("{}: {}", action.name(), e));
+            }
+        }
+    }
+
+    let finished = HooksFinished {
+        gid: context.gid,
+        paths: context
+            .entries
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect(),
+        errors,
+    };
+    if let Err(e) = app_handle.emit("completion-hooks-finished", &finished) {
+        eprintln!("Failed to emit completion-hooks-finished: {}", e);
+    }
+    Ok(())
+}
+
+fn hook_context(gid: &str, status: &Value) -> Option<HookContext> {
+    let dir = PathBuf::from(status.get("dir")?.as_str()?);
+    let files: Vec<PathBuf> = status
+        .get("files")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|file| file.get("selected").and_then(|v| v.as_str()) != Some("false"))
+        .filter_map(|file| file.get("path")?.as_str())
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+
+    // 多文件种子整体作为一个目录处理
+    let torrent_dir = status
+        .pointer("/bittorrent/info/name")
+        .and_then(|v| v.as_str())
+        .filter(|_| status.pointer("/bittorrent/mode").and_then(|v| v.as_str()) == Some("multi"))
+        .map(|name| dir.join(name));
+    let name = match &torrent_dir {
+        Some(path) => path.file_name()?.to_string_lossy().to_string(),
+        None => files[0].file_name()?.to_string_lossy().to_string(),
+    };
+    let entries = match torrent_dir {
+        Some(path) => vec![path],
+        None => files,
+    };
+
+    Some(HookContext {
+        gid: gid.to_string(),
+        name,
+        dir,
+        entries,
+    })
+}
+
+/// 移动到分类目录，目标已存在同名文件时自动加序号
+async fn move_entries(
+    context: &mut HookContext,
+    target_dir: &Path,
+    extensions: &[String],
+    log: &mut HookLog,
+) -> Result<(), AppError> {
+    if !extensions.is_empty() {
+        let entries = context.entries.clone();
+        let files = tokio::task::spawn_blocking(move || collect_files(&entries))
+            .await
+            .map_err(|e| AppError::Process(format!("Move task failed: {}", e)))?;
+        let matched = files.iter().any(|file| {
+            file.extension()
+                .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+                .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(&ext)))
+        });
+        if !matched {
+            log.line("move", "no file matches the extensions, skipped");
+            return Ok(());
+        }
+    }
+
+    tokio::fs::create_dir_all(target_dir).await?;
+    let mut moved = Vec::new();
+    for entry in &context.entries {
+        let Some(file_name) = entry.file_name() else {
+            continue;
+        };
+        let destination = unique_destination(&target_dir.join(file_name));
+        let (from, to) = (entry.clone(), destination.clone());
+        tokio::task::spawn_blocking(move || move_path(&from, &to))
+            .await
+            .map_err(|e| AppError::Process(format!("Move task failed: {}", e)))??;
+        log.line(
+            "move",
+            &format!("{} -> {}", entry.display(), destination.display()),
+        );
+        moved.push(destination);
+    }
+
+    context.entries = moved;
+    context.dir = target_dir.to_path_buf();
+    Ok(())
+}
+
+/// 先尝试重命名，跨磁盘时复制后删除原文件
+fn move_path(from: &Path, to: &Path) -> Result<(), AppError> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursive(from, to)?;
+    if from.is_dir() {
+        std::fs::remove_dir_all(from)?;
+    } else {
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<(), AppError> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// `name.ext` 已存在时依次尝试 `name (1).ext`、`name (2).ext` …
+fn unique_destination(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|index| path.with_file_name(format!("{} ({}){}", stem, index, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// 任务包含的全部文件
+fn collect_files(entries: &[PathBuf]) -> Vec<PathBuf> {
+    fn walk(path: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+        if path.is_dir() {
+            if depth >= MAX_SCAN_DEPTH {
+                return;
+            }
+            for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+                walk(&entry.path(), depth + 1, files);
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    let mut files = Vec::new();
+    for entry in entries {
+        walk(entry, 0, &mut files);
+    }
+    files
+}
+
+/// 解压任务中的全部压缩包
+async fn extract_entries(
+    context: &HookContext,
+    dir: Option<&str>,
+    delete_archive: bool,
+    log: &mut HookLog,
+) -> Result<(), AppError> {
+    let entries = context.entries.clone();
+    let archives: Vec<(PathBuf, ArchiveKind)> =
+        tokio::task::spawn_blocking(move || collect_files(&entries))
+            .await
+            .map_err(|e| AppError::Process(format!("Extract task failed: {}", e)))?
+            .into_iter()
+            .filter_map(|file| ArchiveKind::detect(&file).map(|kind| (file, kind)))
+            .collect();
+    if archives.is_empty() {
+        log.line("extract", "no archive found, skipped");
+        return Ok(());
+    }
+
+    for (archive, kind) in archives {
+        let dest = match dir {
+            Some(dir) => PathBuf::from(dir),
+            None => default_extract_dir(&archive),
+        };
+        let (source, target) = (archive.clone(), dest.clone());
+        tokio::task::spawn_blocking(move || extract_archive(&source, kind, &target))
+            .await
+            .map_err(|e| AppError::Process(format!("Extract task failed: {}", e)))??;
+        log.line(
+            "extract",
+            &format!("{} -> {}", archive.display(), dest.display()),
+        );
+
+        if delete_archive {
+            tokio::fs::remove_file(&archive).await?;
+            log.line("extract", &format!("deleted {}", archive.display()));
+        }
+    }
+    Ok(())
+}
+
+/// 运行用户脚本，输出逐行写入任务日志
+async fn run_command(
+    context: &HookContext,
+    program: &str,
+    args: &[String],
+    log: &mut HookLog,
+) -> Result<(), AppError> {
+    let path = context
+        .entries
+        .first()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dir = context.dir.to_string_lossy().to_string();
+    let args: Vec<String> = if args.is_empty() {
+        vec![context.gid.clone(), path, context.name.clone()]
+    } else {
+        args.iter()
+            .map(|arg| {
+                arg.replace("{gid}", &context.gid)
+                    .replace("{path}", &path)
+                    .replace("{name}", &context.name)
+                    .replace("{dir}", &dir)
+            })
+            .collect()
+    };
+    log.line("command", &format!("{} {:?}", program, args));
+
+    let mut command = tokio::process::Command::new(program);
+    command.args(&args).kill_on_drop(true);
+    if context.dir.is_dir() {
+        command.current_dir(&context.dir);
+    }
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, command.output())
+        .await
+        .map_err(|_| AppError::Process(format!("{} timed out", program)))?
+        .map_err(|e| AppError::Process(format!("Failed to run {}: {}", program, e)))?;
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        log.line("stdout", line);
+    }
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        log.line("stderr", line);
+    }
+    if !output.status.success() {
+        return Err(AppError::Process(format!(
+            "{} exited with {}",
+            program, output.status
+        )));
+    }
+    Ok(())
+}
+
+/// 没有其他任务正在处理、也没有活动或等待中的下载时，延迟后执行电源操作
+async fn power_off_when_idle(
+    app_handle: &tauri::AppHandle,
+    aria2c_state: &Aria2cState,
+    runner: &CompletionHookRunner,
+    action: PowerAction,
+) {
+    if runner.running.load(Ordering::SeqCst) > 0 || !queue_is_empty(aria2c_state).await {
+        return;
+    }
+    // 同一时间只等待一个电源操作
+    if runner.power_pending.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    println!(
+        "Queue is empty, {:?} in {} seconds",
+        action, POWER_ACTION_DELAY_SECS
+    );
+    let pending = PowerActionPending {
+        action,
+        delay_secs: POWER_ACTION_DELAY_SECS,
+    };
+    if let Err(e) = app_handle.emit("power-action-pending", &pending) {
+        eprintln!("Failed to emit power-action-pending: {}", e);
+    }
+
+    let cancelled = tokio::select! {
+        _ = tokio::time::sleep(Duration::from_secs(POWER_ACTION_DELAY_SECS)) => false,
+        _ = runner.cancel_power.notified() => true,
+    };
+    runner.power_pending.store(false, Ordering::SeqCst);
+
+    // 等待期间添加了新任务时不再执行
+    if cancelled || runner.running.load(Ordering::SeqCst) > 0 || !queue_is_empty(aria2c_state).await
+    {
+        println!("Power action {:?} cancelled", action);
+        let _ = app_handle.emit("power-action-cancelled", action);
+        return;
+    }
+
+    if let Err(e) = tokio::task::spawn_blocking(move || power::execute(action))
+        .await
+        .map_err(|e| AppError::Process(format!("Power action failed: {}", e)))
+        .and_then(|result| result)
+    {
+        eprintln!("Failed to execute power action {:?}: {}", action, e);
+    }
+}
+
+/// 没有活动任务，等待列表中也只有暂停的任务
+async fn queue_is_empty(aria2c_state: &Aria2cState) -> bool {
+    let Ok(client) = aria2c_state.client() else {
+        return false;
+    };
+    let active = client.get_active_downloads().await;
+    let waiting = client.get_waiting_downloads(0, 1000).await;
+    match (active, waiting) {
+        (Ok(active), Ok(waiting)) => {
+            active.is_empty() && waiting.iter().all(|task| task.status == "paused")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_existing_destinations() {
+        let dir = std::env::temp_dir().join(format!("dlapp-hooks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let file = dir.join("a.iso");
+        assert_eq!(unique_destination(&file), file);
+        std::fs::write(&file, b"").unwrap();
+        assert_eq!(unique_destination(&file), dir.join("a (1).iso"));
+        std::fs::write(dir.join("a (1).iso"), b"").unwrap();
+        assert_eq!(unique_destination(&file), dir.join("a (2).iso"));
+
+        // 没有扩展名的目录
+        let folder = dir.join("folder");
+        std::fs::create_dir(&folder).unwrap();
+        assert_eq!(unique_destination(&folder), dir.join("folder (1)"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use crate::launch::deep_link::DeepLink;
+use crate::launch::handoff::{show_main_window, HandoffMessage};
+use crate::launch::target::LaunchTarget;
+use crate::torrent::magnet::MagnetLink;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::Emitter;
+
+/// 启动参数对应的操作，由前端打开添加下载对话框
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LaunchAction {
+    AddUrls {
+        urls: Vec<String>,
+    },
+    AddMagnet {
+        uri: String,
+    },
+    /// 本地的种子或 Metalink 文件
+    OpenFile {
+        path: String,
+    },
+}
+
+/// 无法处理的启动参数，通过 `launch-error` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+struct LaunchError {
+    target: LaunchTarget,
+    message: String,
+}
+
+/// 等待前端取走的启动操作
+///
+/// 应用启动时前端还没有加载，事件会丢失，因此启动参数和第二个实例转交的参数都先放进队列，
+/// 再发送 `launch-actions` 通知，前端加载后和收到通知时调用 `take_launch_actions` 取走。
+#[derive(Clone, Default)]
+pub struct LaunchQueue {
+    pending: Arc<Mutex<Vec<LaunchAction>>>,
+}
+
+impl LaunchQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn take(&self) -> Vec<LaunchAction> {
+        self.pending
+            .lock()
+            .map(|mut pending| std::mem::take(&mut *pending))
+            .unwrap_or_default()
+    }
+
+    fn push(&self, actions: Vec<LaunchAction>) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.extend(actions);
+        }
+    }
+}
+
+/// 处理启动参数：启动时和第二个实例转交时走同一流程
+///
+/// 有可执行的操作或 `dlapp://show` 时显示并聚焦主窗口，返回是否显示了窗口
+pub fn dispatch_launch(
+    app_handle: &tauri::AppHandle,
+    queue: &LaunchQueue,
+    message: HandoffMessage,
+) -> bool {
+    let mut actions = Vec::new();
+    let mut show = false;
+
+    for target in message.targets {
+        match resolve_target(&target) {
+            Ok(Some(action)) => actions.push(action),
+            Ok(None) => show = true,
+            Err(message) => {
+                eprintln!("Ignoring launch target {:?}: {}", target, message);
+                let error = LaunchError { target, message };
+                if let Err(e) = app_handle.emit("launch-error", &error) {
+                    eprintln!("Failed to emit launch-error: {}", e);
+                }
+            }
+        }
+    }
+
+    if !actions.is_empty() {
+        queue.push(actions);
+        if let Err(e) = app_handle.emit("launch-actions", ()) {
+            eprintln!("Failed to emit launch-actions: {}", e);
+        }
+        show = true;
+    }
+    if show {
+        show_main_window(app_handle);
+    }
+    show
+}
+
+/// 启动目标转为操作，`Ok(None)` 表示只需要显示窗口
+fn resolve_target(target: &LaunchTarget) -> Result<Option<LaunchAction>, String> {
+    match target {
+        LaunchTarget::File { path } => Ok(Some(LaunchAction::OpenFile { path: path.clone() })),
+        LaunchTarget::Url { url } => Ok(Some(LaunchAction::AddUrls {
+            urls: vec![url.clone()],
+        })),
+        LaunchTarget::Magnet { uri } => {
+            MagnetLink::parse(uri).map_err(|e| e.to_string())?;
+            Ok(Some(LaunchAction::AddMagnet { uri: uri.clone() }))
+        }
+        LaunchTarget::DeepLink { url } => match DeepLink::parse(url).map_err(|e| e.to_string())? {
+            DeepLink::Add { urls } => Ok(Some(LaunchAction::AddUrls { urls })),
+            DeepLink::Magnet { uri } => Ok(Some(LaunchAction::AddMagnet { uri })),
+            DeepLink::OpenTorrent { file } => {
+                resolve_torrent_file(&file).map(|path| Some(LaunchAction::OpenFile { path }))
+            }
+            DeepLink::Show => Ok(None),
+        },
+    }
+}
+
+/// 深度链接中的种子文件名相对于浏览器的下载目录
+fn resolve_torrent_file(file: &str) -> Result<String, String> {
+    let path = Path::new(file);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        dirs::download_dir()
+            .ok_or_else(|| "Unable to locate the download directory".to_string())?
+            .join(path)
+    };
+    if !path.is_file() {
+        return Err(format!("Torrent file not found: {}", path.display()));
+    }
+    Ok(path.to_string_lossy().to_string())
+}
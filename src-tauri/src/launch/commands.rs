@@ -0,0 +1,10 @@
+use crate::error::AppError;
+use crate::launch::actions::{LaunchAction, LaunchQueue};
+
+/// 取走等待处理的启动操作
+#[tauri::command]
+pub async fn take_launch_actions(
+    queue: tauri::State<'_, LaunchQueue>,
+) -> Result<Vec<LaunchAction>, AppError> {
+    Ok(queue.take())
+}
@@ -0,0 +1,205 @@
+use crate::error::AppError;
+use crate::torrent::magnet::MagnetLink;
+
+/// 深度链接的协议名
+pub const SCHEME: &str = "dlapp";
+
+/// 深度链接的长度上限
+const MAX_LEN: usize = 8 * 1024;
+
+/// `add` 允许的下载地址协议
+const ALLOWED_URL_SCHEMES: [&str; 4] = ["http", "https", "ftp", "sftp"];
+
+/// 解析后的 `dlapp://` 深度链接
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLink {
+    /// `dlapp://add?url=<地址>`，可以有多个 `url` 参数
+    Add { urls: Vec<String> },
+    /// `dlapp://open=<文件>.torrent` 或 `dlapp://?open=<文件>.torrent`，
+    /// 文件为绝对路径或浏览器下载目录中的文件名
+    OpenTorrent { file: String },
+    /// `dlapp://magnet?xt=urn:btih:…`，查询参数与磁力链接相同
+    Magnet { uri: String },
+    /// `dlapp://show`，只显示主窗口
+    Show,
+}
+
+impl DeepLink {
+    pub fn parse(link: &str) -> Result<Self, AppError> {
+        let link = link.trim();
+        if link.len() > MAX_LEN {
+            return Err(invalid("link is too long"));
+        }
+        let rest = strip_scheme(link).ok_or_else(|| invalid("expected the dlapp:// scheme"))?;
+
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let action = action.trim_end_matches('/');
+        // `dlapp://open=<文件>` 把参数写在了主机部分
+        let (action, inline_value) = match action.split_once('=') {
+            Some((action, value)) => (action, Some(decode(value))),
+            None => (action, None),
+        };
+        let params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        match action.to_ascii_lowercase().as_str() {
+            "add" => {
+                let urls: Vec<String> = params
+                    .iter()
+                    .filter(|(key, _)| key == "url")
+                    .map(|(_, value)| validate_url(value))
+                    .collect::<Result<_, _>>()?;
+                if urls.is_empty() {
+                    return Err(invalid("add requires at least one url parameter"));
+                }
+                Ok(DeepLink::Add { urls })
+            }
+            "open" => {
+                let file = inline_value
+                    .or_else(|| param("file"))
+                    .ok_or_else(|| invalid("open requires a torrent file"))?;
+                validate_torrent_file(&file).map(|file| DeepLink::OpenTorrent { file })
+            }
+            "" if param("open").is_some() => {
+                let file = param("open").unwrap_or_default();
+                validate_torrent_file(&file).map(|file| DeepLink::OpenTorrent { file })
+            }
+            "magnet" => {
+                let uri = format!("magnet:?{}", query);
+                MagnetLink::parse(&uri)?;
+                Ok(DeepLink::Magnet { uri })
+            }
+            "show" => Ok(DeepLink::Show),
+            "" => Err(invalid("missing action")),
+            other => Err(invalid(&format!("unknown action {}", other))),
+        }
+    }
+}
+
+fn invalid(reason: &str) -> AppError {
+    AppError::InvalidInput(format!("Invalid dlapp link: {}", reason))
+}
+
+/// 去掉 `dlapp://`（协议名不区分大小写，也接受 `dlapp:` 后不带斜杠）
+fn strip_scheme(link: &str) -> Option<&str> {
+    let (scheme, rest) = link.split_once(':')?;
+    if !scheme.eq_ignore_ascii_case(SCHEME) {
+        return None;
+    }
+    Some(rest.trim_start_matches('/'))
+}
+
+fn decode(value: &str) -> String {
+    url::form_urlencoded::parse(format!("v={}", value).as_bytes())
+        .next()
+        .map(|(_, value)| value.to_string())
+        .unwrap_or_default()
+}
+
+fn validate_url(value: &str) -> Result<String, AppError> {
+    let url = url::Url::parse(value.trim())
+        .map_err(|e| invalid(&format!("invalid url {} ({})", value, e)))?;
+    if !ALLOWED_URL_SCHEMES.contains(&url.scheme()) {
+        return Err(invalid(&format!("unsupported url scheme {}", url.scheme())));
+    }
+    Ok(url.to_string())
+}
+
+/// 只允许 `.torrent` 文件，文件名中不能有 `..`，避免指向下载目录以外的位置
+fn validate_torrent_file(file: &str) -> Result<String, AppError> {
+    let file = file.trim();
+    if file.is_empty() || !file.to_ascii_lowercase().ends_with(".torrent") {
+        return Err(invalid("open only accepts .torrent files"));
+    }
+    if std::path::Path::new(file)
+        .components()
+        .any(|component| matches!(component, std::path::Component::ParentDir))
+    {
+        return Err(invalid("torrent path must not contain .."));
+    }
+    Ok(file.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_add_with_several_urls() {
+        let link = DeepLink::parse(
+            "dlapp://add?url=https%3A%2F%2Fexample.com%2Fa.iso&url=ftp://example.com/b.zip",
+        )
+        .unwrap();
+        assert_eq!(
+            link,
+            DeepLink::Add {
+                urls: vec![
+                    "https://example.com/a.iso".to_string(),
+                    "ftp://example.com/b.zip".to_string()
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn parses_open_forms() {
+        let expected = DeepLink::OpenTorrent {
+            file: "my file.torrent".to_string(),
+        };
+        assert_eq!(
+            DeepLink::parse("dlapp://open=my%20file.torrent").unwrap(),
+            expected
+        );
+        assert_eq!(
+            DeepLink::parse("dlapp://?open=my%20file.torrent").unwrap(),
+            expected
+        );
+        assert_eq!(
+            DeepLink::parse("dlapp://open?file=my+file.torrent").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn parses_magnet_and_show() {
+        let hash = "ca41b533e1b532b4d8d6f8db8e18b0d3d26ea1b7";
+        assert_eq!(
+            DeepLink::parse(&format!("dlapp://magnet?xt=urn:btih:{}&dn=x", hash)).unwrap(),
+            DeepLink::Magnet {
+                uri: format!("magnet:?xt=urn:btih:{}&dn=x", hash)
+            }
+        );
+        assert_eq!(DeepLink::parse("DLAPP://show/").unwrap(), DeepLink::Show);
+        assert_eq!(DeepLink::parse("dlapp:show").unwrap(), DeepLink::Show);
+    }
+
+    #[test]
+    fn rejects_unsafe_links() {
+        for link in [
+            "https://example.com/add",
+            "dlapp://",
+            "dlapp://add",
+            "dlapp://delete?gid=1",
+            "dlapp://add?url=javascript:alert(1)",
+            "dlapp://add?url=file:///etc/passwd",
+            "dlapp://open=..%2F..%2Fsecret.torrent",
+            "dlapp://?open=a/../b.torrent",
+            "dlapp://open=setup.exe",
+        ] {
+            assert!(
+                matches!(DeepLink::parse(link), Err(AppError::InvalidInput(_))),
+                "{}",
+                link
+            );
+        }
+        let long = format!("dlapp://add?url=https://e.com/{}", "a".repeat(MAX_LEN));
+        assert!(DeepLink::parse(&long).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::bridge::server::BRIDGE_ADDR;
+use crate::bridge::token::{read_token, token_path};
+use crate::launch::actions::{dispatch_launch, LaunchQueue};
+use crate::launch::target::{targets_from_args, LaunchTarget};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::Manager;
+
+/// 已有实例接收启动参数的路径
+pub const HANDOFF_PATH: &str = "/handoff";
+
+/// 第二个实例的退出码：参数已转交给正在运行的实例
+pub const EXIT_HANDED_OFF: i32 = 0;
+
+/// 第二个实例的退出码：已有实例在运行，但转交失败
+pub const EXIT_HANDOFF_FAILED: i32 = 3;
+
+/// 检测已有实例的超时时间
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 转交请求的超时时间
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 第二个实例转交给已有实例的启动参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffMessage {
+    pub targets: Vec<LaunchTarget>,
+    /// 第二个实例的工作目录
+    pub cwd: Option<String>,
+}
+
+impl HandoffMessage {
+    /// 从当前进程的参数和工作目录生成，相对路径在这里解析为绝对路径
+    pub fn from_env() -> Self {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let cwd = std::env::current_dir().ok();
+        Self {
+            targets: targets_from_args(&args, cwd.as_deref()),
+            cwd: cwd.map(|cwd| cwd.to_string_lossy().to_string()),
+        }
+    }
+}
+
+/// 检测是否已有实例在运行，有则转交启动参数并返回退出码，没有则返回 `None`
+pub fn hand_off_to_running_instance() -> Option<i32> {
+    let client = reqwest::blocking::Client::new();
+    let running = client
+        .get(format!("http://{}/health", BRIDGE_ADDR))
+        .timeout(HEALTH_TIMEOUT)
+        .send()
+        .is_ok_and(|response| response.status().is_success());
+    if !running {
+        return None;
+    }
+
+    let message = HandoffMessage::from_env();
+    println!("Another instance is running, handing off: {:?}", message);
+
+    let token = match token_path().and_then(|path| read_token(&path)) {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("Failed to read bridge token: {}", e);
+            return Some(EXIT_HANDOFF_FAILED);
+        }
+    };
+
+    let result = client
+        .post(format!("http://{}{}", BRIDGE_ADDR, HANDOFF_PATH))
+        .bearer_auth(token)
+        .timeout(HANDOFF_TIMEOUT)
+        .json(&message)
+        .send();
+    match result {
+        Ok(response) if response.status().is_success() => Some(EXIT_HANDED_OFF),
+        Ok(response) => {
+            eprintln!("Running instance rejected handoff: {}", response.status());
+            Some(EXIT_HANDOFF_FAILED)
+        }
+        Err(e) => {
+            eprintln!("Failed to hand off to running instance: {}", e);
+            Some(EXIT_HANDOFF_FAILED)
+        }
+    }
+}
+
+/// 处理第二个实例转交的参数，与启动参数走同一流程；没有参数时也显示并聚焦主窗口
+pub fn handle_handoff(app_handle: &tauri::AppHandle, message: HandoffMessage) {
+    println!("Received handoff: {:?}", message);
+    let shown = match app_handle.try_state::<LaunchQueue>() {
+        Some(queue) => dispatch_launch(app_handle, &queue, message),
+        None => false,
+    };
+    if !shown {
+        show_main_window(app_handle);
+    }
+}
+
+/// 从托盘或最小化状态恢复主窗口并聚焦
+pub fn show_main_window(app_handle: &tauri::AppHandle) {
+    let Some(window) = app_handle.get_webview_window("main") else {
+        return;
+    };
+    if window.is_minimized().unwrap_or(false) {
+        let _ = window.unminimize();
+    }
+    if let Err(e) = window.show().and_then(|_| window.set_focus()) {
+        eprintln!("Failed to focus main window: {}", e);
+    }
+}
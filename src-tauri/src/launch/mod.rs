@@ -0,0 +1,5 @@
+pub mod actions;
+pub mod commands;
+pub mod deep_link;
+pub mod handoff;
+pub mod target;
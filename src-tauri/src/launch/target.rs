@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 命令行参数表示的启动目标，由前端打开对应的添加下载对话框
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LaunchTarget {
+    /// 本地文件（种子、Metalink），为绝对路径
+    File { path: String },
+    /// HTTP/HTTPS/FTP 地址
+    Url { url: String },
+    /// 磁力链接
+    Magnet { uri: String },
+    /// `dlapp://` 链接
+    DeepLink { url: String },
+}
+
+impl LaunchTarget {
+    /// 解析单个参数，相对路径按 `cwd` 解析；以 `-` 开头的选项忽略
+    pub fn from_arg(arg: &str, cwd: Option<&Path>) -> Option<Self> {
+        let arg = arg.trim();
+        if arg.is_empty() || arg.starts_with('-') {
+            return None;
+        }
+
+        let lower = arg.to_ascii_lowercase();
+        if lower.starts_with("dlapp:") {
+            return Some(LaunchTarget::DeepLink {
+                url: arg.to_string(),
+            });
+        }
+        if lower.starts_with("magnet:") {
+            return Some(LaunchTarget::Magnet {
+                uri: arg.to_string(),
+            });
+        }
+        if ["http://", "https://", "ftp://", "sftp://"]
+            .iter()
+            .any(|scheme| lower.starts_with(scheme))
+        {
+            return Some(LaunchTarget::Url {
+                url: arg.to_string(),
+            });
+        }
+
+        let path = Path::new(arg);
+        let path = match cwd {
+            Some(cwd) if path.is_relative() => cwd.join(path),
+            _ => path.to_path_buf(),
+        };
+        Some(LaunchTarget::File {
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+}
+
+/// 解析程序参数（不含程序路径）
+pub fn targets_from_args(args: &[String], cwd: Option<&Path>) -> Vec<LaunchTarget> {
+    args.iter()
+        .filter_map(|arg| LaunchTarget::from_arg(arg, cwd))
+        .collect()
+}
@@ -1,106 +1,90 @@
 mod aria2c;
+mod bridge;
+mod checksum;
 mod config;
+mod error;
+mod history;
+mod hooks;
+mod launch;
+mod schedule;
+mod shutdown;
+mod torrent;
 use crate::aria2c::{
-    add_batch_downloads, add_download_magnet, add_download_magnet_simple, add_download_torrent,
-    add_download_torrent_base64, add_download_torrent_simple, add_download_url,
-    add_download_url_simple, change_global_option, get_active_downloads, get_download_stats,
-    get_download_status, get_files, get_global_options, get_peers, get_stopped_downloads,
-    get_waiting_downloads, pause_download, purge_download_result, remove_download,
-    restart_download, resume_download, tell_status, tell_torrent_info, test_aria2c_connection,
-    test_aria2c_connection_detailed,
+    add_batch_downloads, add_download_magnet, add_download_magnet_simple, add_download_metalink,
+    add_download_metalink_base64, add_download_torrent, add_download_torrent_base64,
+    add_download_torrent_simple, add_download_url, add_download_url_simple, change_global_option,
+    change_task_options, get_active_downloads, get_download_stats, get_download_status, get_files,
+    get_global_options, get_peers, get_stopped_downloads, get_task_options, get_waiting_downloads,
+    list_downloads, move_download, move_downloads_to_front, pause_download, purge_download_result,
+    remove_download, restart_download, resume_download, select_download_files, tell_status,
+    test_aria2c_connection, test_aria2c_connection_detailed,
 };
 
-use crate::aria2c::{get_aria2c_info, start_aria2c, stop_aria2c, Aria2cState};
-use crate::config::commands::{ get_download_settings, update_download_settings};
+use crate::aria2c::{
+    connect_external_daemon, disconnect_external_daemon, endpoint_from_settings, get_aria2c_info,
+    start_aria2c, start_notification_listener, stop_aria2c, Aria2cEvents, Aria2cState,
+};
+use crate::bridge::api::BridgeApi;
+use crate::bridge::commands::{get_bridge_info, regenerate_bridge_token, update_bridge_origins};
+use crate::bridge::events::{start_event_hub, EventHub};
+use crate::bridge::guard::BridgeGuard;
+use crate::bridge::server::start_http_server;
+use crate::bridge::token::{token_path, BridgeToken};
+use crate::checksum::{start_checksum_verifier, ChecksumOutcomes};
+use crate::config::commands::{
+    get_categories, get_download_settings, update_categories, update_daemon_settings,
+    update_download_settings,
+};
 use crate::config::settings::DownloadSettings;
-use rouille::Response;
-use std::env;
+use crate::history::commands::{
+    clear_download_history, delete_download_history, get_download_history, search_download_history,
+};
+use crate::history::recorder::start_history_recorder;
+use crate::history::store::HistoryStore;
+use crate::hooks::commands::{
+    cancel_power_action, get_completion_hooks, get_hook_log, update_completion_hooks,
+};
+use crate::hooks::runner::{start_completion_hooks, CompletionHookRunner};
+use crate::launch::actions::{dispatch_launch, LaunchQueue};
+use crate::launch::commands::take_launch_actions;
+use crate::launch::handoff::HandoffMessage;
+use crate::schedule::commands::{
+    cancel_scheduled_action, get_active_speed_limits, get_queue_schedule, get_speed_schedule,
+    schedule_queue_action, schedule_task_start, update_queue_rules, update_speed_schedule,
+};
+use crate::schedule::scheduler::{start_scheduler, Scheduler};
+use crate::torrent::cache::TorrentCache;
+use crate::torrent::commands::{fetch_magnet_metadata, parse_magnet_link, tell_torrent_info};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::tray::TrayIconEvent;
 use tauri::{
     menu::{Menu, MenuItem},
     tray::TrayIconBuilder,
-    Emitter, Manager,
+    Manager,
 };
 
-fn start_http_server(app_handle: tauri::AppHandle) {
-    rouille::start_server("127.0.0.1:6567", move |request| {
-        println!("Received request: {} {}", request.method(), request.url());
-
-        match (request.method(), request.url().as_ref()) {
-            ("GET", "/health") => Response::text("Server is running"),
-
-            ("POST", "/message") => {
-                // 解析 JSON 消息
-                let input_data: Result<serde_json::Value, _> = rouille::input::json_input(request);
-
-                match input_data {
-                    Ok(message) => {
-                        // 发送消息到前端
-                        let app_handle_clone = app_handle.clone();
-                        tauri::async_runtime::spawn(async move {
-                            app_handle_clone
-                                .emit("http-message", message)
-                                .expect("failed to emit event");
-                        });
-
-                        Response::json(&serde_json::json!({
-                            "status": "success",
-                            "message": "Message sent to frontend"
-                        }))
-                    }
-                    Err(_) => Response::json(&serde_json::json!({
-                        "status": "error",
-                        "message": "Invalid JSON"
-                    }))
-                    .with_status_code(400),
-                }
-            }
-
-            ("POST", "/command") => {
-                // 处理其他命令
-                let input_data: Result<serde_json::Value, _> = rouille::input::json_input(request);
-
-                match input_data {
-                    Ok(data) => {
-                        let app_handle_clone = app_handle.clone();
-                        tauri::async_runtime::spawn(async move {
-                            app_handle_clone
-                                .emit("http-command", &data)
-                                .expect("failed to emit event");
-                        });
-
-                        Response::json(&serde_json::json!({
-                            "status": "success"
-                        }))
-                    }
-                    Err(_) => Response::text("Invalid JSON").with_status_code(400),
-                }
-            }
-
-            _ => Response::text("Not found").with_status_code(404),
-        }
-    });
+/// 已有实例在运行时转交启动参数，返回第二个实例应使用的退出码
+pub fn hand_off_to_running_instance() -> Option<i32> {
+    launch::handoff::hand_off_to_running_instance()
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // 运行参数检测， url 启动的模式。
-    let aria2c_state = Aria2cState::new();
-    let args: Vec<String> = env::args().collect();
-    // let mut params = "dlapp://open=44970-exit-the-abyss_20250709.torrent".to_string();
-    // let mut params = "dlapp://?open=49619-pigface_20250919.torrent".to_string();
-    let mut params = "".to_string();
-    if args.len() > 1 {
-        params = args[1].clone();
-    }
-    // 初始化设置状态
-    let settings_state: Arc<Mutex<DownloadSettings>> =
-        Arc::new(Mutex::new(DownloadSettings::load().unwrap_or_else(|e| {
-            eprintln!("Failed to load settings, using default: {}", e);
-            DownloadSettings::default()
-        })));
+    // 启动参数（文件、链接、dlapp:// 深度链接）等前端加载后取走
+    let launch_queue = LaunchQueue::new();
+    // 初始化设置状态；损坏的配置文件在 load 中已备份并换成默认配置，
+    // 这里只剩配置目录无法读写的情况，此时默认配置也无法保存
+    let settings = DownloadSettings::load().unwrap_or_else(|e| {
+        eprintln!("Failed to load settings, using unsaved defaults: {}", e);
+        DownloadSettings::default()
+    });
+
+    // RPC 地址和密钥来自设置（本地 aria2c 或外部守护进程），所有客户端共用同一份状态
+    let aria2c_state = Aria2cState::new(endpoint_from_settings(&settings));
+    let settings_state: Arc<Mutex<DownloadSettings>> = Arc::new(Mutex::new(settings));
+    // aria2c 下载通知的分发中心，前端事件和历史记录共用
+    let aria2c_events = Aria2cEvents::new();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_process::init())
@@ -115,19 +99,26 @@ pub fn run() {
         .plugin(tauri_plugin_notification::init())
         .manage(aria2c_state.clone())
         .manage(settings_state.clone())
+        .manage(aria2c_events.clone())
+        .manage(launch_queue.clone())
         .invoke_handler(tauri::generate_handler![
             // Aria2c 命令
             start_aria2c,
             stop_aria2c,
             get_aria2c_info,
+            connect_external_daemon,
+            disconnect_external_daemon,
             add_download_url,
             add_download_torrent,
             add_download_torrent_base64,
+            add_download_metalink,
+            add_download_metalink_base64,
             add_download_magnet,
             get_download_status,
             get_active_downloads,
             get_waiting_downloads,
             get_stopped_downloads,
+            list_downloads,
             pause_download,
             resume_download,
             restart_download,
@@ -135,11 +126,16 @@ pub fn run() {
             purge_download_result,
             get_peers,
             get_files,
+            select_download_files,
             get_download_stats,
             add_batch_downloads,
             test_aria2c_connection,
             test_aria2c_connection_detailed,
             tell_status,
+            get_task_options,
+            change_task_options,
+            move_download,
+            move_downloads_to_front,
             // 兼容性命令
             add_download_url_simple,
             add_download_torrent_simple,
@@ -150,8 +146,36 @@ pub fn run() {
             // 设置命令
             get_download_settings,
             update_download_settings,
+            update_daemon_settings,
+            // 本地 HTTP 接口命令
+            get_bridge_info,
+            regenerate_bridge_token,
+            update_bridge_origins,
+            // 下载分类命令
+            get_categories,
+            update_categories,
+            // 下载历史命令
+            get_download_history,
+            search_download_history,
+            delete_download_history,
+            clear_download_history,
             // 主动命令
             tell_torrent_info,
+            parse_magnet_link,
+            fetch_magnet_metadata,
+            get_speed_schedule,
+            update_speed_schedule,
+            get_active_speed_limits,
+            get_queue_schedule,
+            update_queue_rules,
+            schedule_queue_action,
+            schedule_task_start,
+            cancel_scheduled_action,
+            get_completion_hooks,
+            update_completion_hooks,
+            get_hook_log,
+            cancel_power_action,
+            take_launch_actions,
 
         ])
         .on_window_event( move |app, event| match event {
@@ -168,11 +192,86 @@ pub fn run() {
             let aria2c_state_clone = aria2c_state.clone();
             let settings_state_clone = settings_state.clone();
 
+            // 收到系统退出信号时走正常退出流程，保证 aria2c 保存会话
+            shutdown::listen_for_shutdown_signals(app.handle().clone());
+
+            // 下载历史保存在应用数据目录，独立于 aria2 的已停止列表
+            let history_path = app.path().app_data_dir()?.join("history.db");
+            let history_store = HistoryStore::open(&history_path)?;
+            start_history_recorder(
+                history_store.clone(),
+                aria2c_state.clone(),
+                aria2c_events.clone(),
+            );
+
+            // 设置了期望摘要的任务完成后再次校验，失败时更新历史
+            let checksum_outcomes = ChecksumOutcomes::new();
+            start_checksum_verifier(
+                app.handle().clone(),
+                aria2c_state.clone(),
+                aria2c_events.clone(),
+                history_store.clone(),
+                checksum_outcomes.clone(),
+            );
+            app.manage(history_store);
+
+            // 添加的种子保存一份副本，重启 BT 任务时使用
+            let torrent_cache = TorrentCache::new(app.path().app_data_dir()?.join("torrents"));
+            app.manage(torrent_cache.clone());
+
+            // 本地 HTTP 接口需要令牌，令牌保存在应用数据目录
+            let bridge_token = Arc::new(BridgeToken::load_or_create(token_path()?)?);
+            app.manage(bridge_token.clone());
+            let bridge_guard = BridgeGuard::new(bridge_token, settings_state.clone());
+            let bridge_events = EventHub::new();
+            start_event_hub(
+                bridge_events.clone(),
+                aria2c_state.clone(),
+                aria2c_events.clone(),
+            );
+            let bridge_api = BridgeApi::new(
+                aria2c_state.clone(),
+                settings_state.clone(),
+                torrent_cache,
+                bridge_events,
+            );
             let app_handle = app.handle().clone();
             thread::spawn(move || {
-                start_http_server(app_handle);
+                start_http_server(app_handle, bridge_guard, bridge_api);
             });
 
+            // 按限速计划在时间段边界切换全局限速
+            let scheduler = Scheduler::new();
+            start_scheduler(
+                app.handle().clone(),
+                aria2c_state.clone(),
+                settings_state.clone(),
+                scheduler.clone(),
+            );
+            app.manage(scheduler);
+
+            // 任务完成后移动、解压、运行脚本，队列为空时关机或睡眠
+            // 设置了期望摘要的任务等校验结束后再处理
+            let hook_runner = CompletionHookRunner::new(
+                app.path().app_log_dir()?.join("hooks"),
+                checksum_outcomes,
+            );
+            start_completion_hooks(
+                app.handle().clone(),
+                aria2c_state.clone(),
+                settings_state.clone(),
+                aria2c_events.clone(),
+                hook_runner.clone(),
+            );
+            app.manage(hook_runner);
+
+            // 订阅 aria2c 的 WebSocket 通知，转发为前端事件并写入历史
+            start_notification_listener(
+                app.handle().clone(),
+                aria2c_state.clone(),
+                aria2c_events.clone(),
+            );
+
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 if let Err(e) = aria2c_state_clone.start_aria2c(app_handle).await {
@@ -184,8 +283,15 @@ pub fn run() {
                     let global_config = settings.get_global_aria2c_config();
                     if !global_config.is_empty() {
                         let global_config_clone = global_config.clone();
+                        let aria2c_state = aria2c_state_clone.clone();
                         tauri::async_runtime::spawn(async move {
-                            if let Err(e) = change_global_option(global_config_clone).await {
+                            let result = match aria2c_state.client() {
+                                Ok(client) => {
+                                    client.change_global_option(global_config_clone).await
+                                }
+                                Err(e) => Err(e),
+                            };
+                            if let Err(e) = result {
                                 eprintln!("Failed to send global config to aria2c: {}", e);
                             } else {
                                 println!(
@@ -202,11 +308,8 @@ pub fn run() {
             let show_windown = MenuItem::with_id(app, "show_window", "显示", true, None::<&str>)?;
             let menu = Menu::with_items(app, &[&show_windown, &quit_i])?;
 
-            let main_window = app.get_webview("main").unwrap();
-            // main_window.open_devtools();
-            main_window
-                .eval(&format!("window.location.href = '/?params={}'", params))
-                .unwrap();
+            // 启动参数与第二个实例转交的参数走同一流程
+            dispatch_launch(app.handle(), &launch_queue, HandoffMessage::from_env());
 
             // 创建托盘图标并添加菜单
             let _tray = TrayIconBuilder::new()
@@ -230,27 +333,8 @@ pub fn run() {
                 })
                 .on_menu_event(|app, event| match event.id.as_ref() {
                     "quit" => {
-                        println!("Quit menu item clicked, stopping aria2c...");
-        
-                        // 先停止 aria2c
-                        let app_handle = app.app_handle().clone();
-                        tauri::async_runtime::block_on(async move {
-                            let state = app_handle.state::<Aria2cState>();
-                            if let Err(e) = state.inner().stop_aria2c().await {
-                                eprintln!("Failed to stop aria2c: {}", e);
-                            } else {
-                                println!("aria2c stopped successfully");
-                            }
-                        });
-                        
-                        // 等待一小段时间确保 aria2c 完全停止
-                        thread::sleep(std::time::Duration::from_millis(500));
-                        // 关闭所有窗口
-                        for (_, window) in app.windows().iter() {
-                            let _ = window.close();
-                        }
-                        
-                        // 退出应用
+                        // 退出流程统一在 RunEvent::ExitRequested 中关闭 aria2c
+                        println!("Quit menu item clicked, exiting...");
                         app.exit(0);
                     }
                     "show_window" => {
@@ -273,6 +357,11 @@ pub fn run() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                shutdown::shutdown_aria2c(app);
+            }
+        });
 }
@@ -0,0 +1,184 @@
+use crate::aria2c::Aria2cState;
+use crate::config::settings::DownloadSettings;
+use crate::error::AppError;
+use crate::schedule::queue::{
+    resolve_run_at, PendingAction, QueueAction, QueueRule, QueueSchedule, ScheduledAction,
+};
+use crate::schedule::scheduler::Scheduler;
+use crate::schedule::speed::{SpeedLimits, SpeedSchedule};
+use std::sync::{Arc, Mutex};
+
+/// 获取限速计划
+#[tauri::command]
+pub async fn get_speed_schedule(
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<SpeedSchedule, AppError> {
+    Ok(settings_state.lock()?.speed_schedule.clone())
+}
+
+/// 更新限速计划，保存后立即按当前时间切换限速
+#[tauri::command]
+pub async fn update_speed_schedule(
+    schedule: SpeedSchedule,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    scheduler: tauri::State<'_, Scheduler>,
+) -> Result<SpeedSchedule, AppError> {
+    if schedule
+        .profiles
+        .iter()
+        .any(|profile| profile.name.trim().is_empty())
+    {
+        return Err(AppError::InvalidInput(
+            "Speed profile name must not be empty".to_string(),
+        ));
+    }
+
+    {
+        let mut settings = settings_state.lock()?;
+        settings.speed_schedule = schedule.clone();
+        settings.save()?;
+    }
+    scheduler.wake();
+
+    Ok(schedule)
+}
+
+/// 获取当前生效的限速
+#[tauri::command]
+pub async fn get_active_speed_limits(
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<SpeedLimits, AppError> {
+    let settings = settings_state.lock()?;
+    Ok(settings.speed_limits_at(chrono::Local::now().naive_local()))
+}
+
+/// 获取队列启停计划
+#[tauri::command]
+pub async fn get_queue_schedule(
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<QueueSchedule, AppError> {
+    Ok(settings_state.lock()?.queue_schedule.clone())
+}
+
+/// 更新每周重复的队列规则
+#[tauri::command]
+pub async fn update_queue_rules(
+    rules: Vec<QueueRule>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    scheduler: tauri::State<'_, Scheduler>,
+) -> Result<QueueSchedule, AppError> {
+    let schedule = {
+        let mut settings = settings_state.lock()?;
+        settings.queue_schedule.rules = rules;
+        settings.save()?;
+        settings.queue_schedule.clone()
+    };
+    scheduler.wake();
+
+    Ok(schedule)
+}
+
+/// 在指定时刻 (HH:MM) 或延迟指定分钟后暂停或恢复整个队列
+#[tauri::command]
+pub async fn schedule_queue_action(
+    action: QueueAction,
+    at: Option<String>,
+    delay_minutes: Option<u64>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    scheduler: tauri::State<'_, Scheduler>,
+) -> Result<PendingAction, AppError> {
+    add_pending_action(
+        action.into(),
+        at.as_deref(),
+        delay_minutes,
+        &settings_state,
+        &scheduler,
+    )
+}
+
+/// 在指定时刻 (HH:MM) 或延迟指定分钟后开始任务，任务正在下载或等待时先暂停
+#[tauri::command]
+pub async fn schedule_task_start(
+    gid: String,
+    at: Option<String>,
+    delay_minutes: Option<u64>,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+    scheduler: tauri::State<'_, Scheduler>,
+) -> Result<PendingAction, AppError> {
+    let client = aria2c_state.client()?;
+    let status = client
+        .tell_status(&gid, Some(vec!["status".to_string()]))
+        .await?;
+    match status.get("status").and_then(|v| v.as_str()) {
+        Some("active") | Some("waiting") => {
+            client.pause_download(&gid).await?;
+        }
+        Some("paused") => {}
+        _ => {
+            return Err(AppError::InvalidInput(format!(
+                "Task {} has already stopped and cannot be scheduled",
+                gid
+            )));
+        }
+    }
+
+    // 同一个任务只保留最新的计划
+    settings_state.lock()?.queue_schedule.pending.retain(
+        |pending| !matches!(&pending.action, ScheduledAction::StartTask { gid: g } if *g == gid),
+    );
+    add_pending_action(
+        ScheduledAction::StartTask { gid },
+        at.as_deref(),
+        delay_minutes,
+        &settings_state,
+        &scheduler,
+    )
+}
+
+/// 取消一次性的计划操作
+#[tauri::command]
+pub async fn cancel_scheduled_action(
+    id: String,
+    settings_state: tauri::State<'_, Arc<Mutex<DownloadSettings>>>,
+) -> Result<QueueSchedule, AppError> {
+    let mut settings = settings_state.lock()?;
+    let before = settings.queue_schedule.pending.len();
+    settings
+        .queue_schedule
+        .pending
+        .retain(|pending| pending.id != id);
+    if settings.queue_schedule.pending.len() == before {
+        return Err(AppError::InvalidInput(format!(
+            "Scheduled action not found: {}",
+            id
+        )));
+    }
+    settings.save()?;
+
+    Ok(settings.queue_schedule.clone())
+}
+
+fn add_pending_action(
+    action: ScheduledAction,
+    at: Option<&str>,
+    delay_minutes: Option<u64>,
+    settings_state: &Arc<Mutex<DownloadSettings>>,
+    scheduler: &Scheduler,
+) -> Result<PendingAction, AppError> {
+    let run_at = resolve_run_at(at, delay_minutes, chrono::Local::now())?;
+    let pending = PendingAction {
+        id: format!("{:016x}", rand::random::<u64>()),
+        run_at: run_at.timestamp(),
+        action,
+    };
+
+    {
+        let mut settings = settings_state.lock()?;
+        settings.queue_schedule.pending.push(pending.clone());
+        settings.save()?;
+    }
+    scheduler.wake();
+
+    Ok(pending)
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod queue;
+pub mod scheduler;
+pub mod speed;
+pub mod window;
@@ -0,0 +1,284 @@
+use crate::error::AppError;
+use crate::schedule::window::TimeOfDay;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// 整个队列的操作
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueAction {
+    /// 暂停全部任务 (aria2.forcePauseAll)
+    PauseAll,
+    /// 恢复全部任务 (aria2.unpauseAll)
+    UnpauseAll,
+}
+
+/// 计划执行的操作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScheduledAction {
+    PauseAll,
+    UnpauseAll,
+    /// 到时间后开始指定任务
+    StartTask {
+        gid: String,
+    },
+}
+
+impl From<QueueAction> for ScheduledAction {
+    fn from(action: QueueAction) -> Self {
+        match action {
+            QueueAction::PauseAll => ScheduledAction::PauseAll,
+            QueueAction::UnpauseAll => ScheduledAction::UnpauseAll,
+        }
+    }
+}
+
+/// 每周重复的队列规则，例如 "每天 23:00 恢复全部，07:00 暂停全部"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueRule {
+    /// 生效的星期 ("Mon" … "Sun")，为空表示每天
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub at: TimeOfDay,
+    pub action: QueueAction,
+}
+
+/// 只执行一次的计划操作，执行后从列表中移除
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingAction {
+    pub id: String,
+    /// 执行时间 (Unix 秒)
+    pub run_at: i64,
+    pub action: ScheduledAction,
+}
+
+/// 队列的启停计划，保存在设置文件中，重启应用后继续生效
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueSchedule {
+    #[serde(default)]
+    pub rules: Vec<QueueRule>,
+    #[serde(default)]
+    pub pending: Vec<PendingAction>,
+}
+
+impl QueueRule {
+    /// 不晚于 `now` 的最近一次触发时间
+    fn last_occurrence(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=7)
+            .map(|days_ago| now.date() - Duration::days(days_ago))
+            .filter(|date| self.days.is_empty() || self.days.contains(&date.weekday()))
+            .map(|date| date.and_time(self.at.to_naive_time()))
+            .find(|occurrence| *occurrence <= now)
+    }
+}
+
+impl QueueSchedule {
+    /// 取出 `(since, now]` 之间到期的操作，按执行时间排序，一次性操作从列表中移除
+    ///
+    /// 应用关闭期间到期的一次性操作在下次启动时执行；每周规则只在应用运行期间触发，
+    /// 睡眠期间错过的规则在恢复后按顺序补执行，队列最终处于最近一条规则的状态
+    pub fn take_due(
+        &mut self,
+        since: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> Vec<(Option<String>, ScheduledAction)> {
+        let mut due: Vec<(i64, Option<String>, ScheduledAction)> = Vec::new();
+
+        for rule in &self.rules {
+            if let Some(occurrence) = rule.last_occurrence(now.naive_local()) {
+                if occurrence > since.naive_local() {
+                    let at = occurrence
+                        .and_local_timezone(Local)
+                        .earliest()
+                        .map_or(now.timestamp(), |at| at.timestamp());
+                    due.push((at, None, rule.action.into()));
+                }
+            }
+        }
+
+        let now_ts = now.timestamp();
+        let (ready, waiting): (Vec<_>, Vec<_>) = self
+            .pending
+            .drain(..)
+            .partition(|pending| pending.run_at <= now_ts);
+        self.pending = waiting;
+        due.extend(
+            ready
+                .into_iter()
+                .map(|pending| (pending.run_at, Some(pending.id), pending.action)),
+        );
+
+        due.sort_by_key(|(at, _, _)| *at);
+        due.into_iter()
+            .map(|(_, id, action)| (id, action))
+            .collect()
+    }
+
+    /// 最早的一次性操作的执行时间
+    pub fn next_pending_at(&self) -> Option<i64> {
+        self.pending.iter().map(|pending| pending.run_at).min()
+    }
+}
+
+/// 计算执行时间：指定 "HH:MM" 时为下一次到达该时刻，否则为当前时间加上延迟分钟数
+pub fn resolve_run_at(
+    at: Option<&str>,
+    delay_minutes: Option<u64>,
+    now: DateTime<Local>,
+) -> Result<DateTime<Local>, AppError> {
+    match (at, delay_minutes) {
+        (Some(at), None) => {
+            let time = TimeOfDay::parse(at)
+                .map_err(AppError::InvalidInput)?
+                .to_naive_time();
+            let today = now.date_naive().and_time(time);
+            let candidate = if today > now.naive_local() {
+                today
+            } else {
+                today + Duration::days(1)
+            };
+            // 夏令时切换时本地时间可能不存在，取最早的有效时间
+            candidate
+                .and_local_timezone(Local)
+                .earliest()
+                .ok_or_else(|| {
+                    AppError::InvalidInput(format!(
+                        "Time {} does not exist in the local time zone",
+                        at
+                    ))
+                })
+        }
+        (None, Some(minutes)) => Ok(now + Duration::minutes(minutes as i64)),
+        _ => Err(AppError::InvalidInput(
+            "Specify exactly one of a time (HH:MM) or a delay in minutes".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// 2024-01-01 是星期一
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 1, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn rule(days: Vec<Weekday>, time: &str, action: QueueAction) -> QueueRule {
+        QueueRule {
+            days,
+            at: TimeOfDay::parse(time).unwrap(),
+            action,
+        }
+    }
+
+    fn pending(id: &str, run_at: DateTime<Local>) -> PendingAction {
+        PendingAction {
+            id: id.to_string(),
+            run_at: run_at.timestamp(),
+            action: ScheduledAction::StartTask {
+                gid: id.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn takes_due_actions_in_order() {
+        let mut schedule = QueueSchedule {
+            rules: vec![rule(vec![], "10:00", QueueAction::PauseAll)],
+            pending: vec![
+                pending("late", at(1, 10, 30)),
+                pending("early", at(1, 9, 30)),
+                pending("future", at(1, 12, 0)),
+            ],
+        };
+
+        let due = schedule.take_due(at(1, 9, 0), at(1, 11, 0));
+        assert_eq!(
+            due,
+            vec![
+                (
+                    Some("early".to_string()),
+                    ScheduledAction::StartTask {
+                        gid: "early".to_string()
+                    }
+                ),
+                (None, ScheduledAction::PauseAll),
+                (
+                    Some("late".to_string()),
+                    ScheduledAction::StartTask {
+                        gid: "late".to_string()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(schedule.pending, vec![pending("future", at(1, 12, 0))]);
+        assert_eq!(schedule.next_pending_at(), Some(at(1, 12, 0).timestamp()));
+
+        // 已执行过的规则不会再次触发
+        assert!(schedule.take_due(at(1, 11, 0), at(1, 11, 1)).is_empty());
+    }
+
+    #[test]
+    fn catches_up_missed_rules_after_wake() {
+        let mut schedule = QueueSchedule {
+            rules: vec![
+                rule(vec![], "07:00", QueueAction::PauseAll),
+                rule(vec![], "23:00", QueueAction::UnpauseAll),
+                rule(vec![Weekday::Sat], "12:00", QueueAction::UnpauseAll),
+            ],
+            pending: vec![],
+        };
+
+        // 周一 22:00 睡眠，周二 08:00 唤醒：先恢复再暂停，队列最终处于暂停状态
+        let due = schedule.take_due(at(1, 22, 0), at(2, 8, 0));
+        assert_eq!(
+            due,
+            vec![
+                (None, ScheduledAction::UnpauseAll),
+                (None, ScheduledAction::PauseAll)
+            ]
+        );
+
+        // 睡眠多天时每条规则只补执行最近的一次
+        let due = schedule.take_due(at(1, 22, 0), at(8, 8, 0));
+        assert_eq!(
+            due,
+            vec![
+                (None, ScheduledAction::UnpauseAll),
+                (None, ScheduledAction::UnpauseAll),
+                (None, ScheduledAction::PauseAll)
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_run_times() {
+        let now = at(1, 12, 0);
+        assert_eq!(
+            resolve_run_at(Some("13:30"), None, now).unwrap(),
+            at(1, 13, 30)
+        );
+        // 今天的时刻已过，顺延到明天
+        assert_eq!(
+            resolve_run_at(Some("12:00"), None, now).unwrap(),
+            at(2, 12, 0)
+        );
+        assert_eq!(resolve_run_at(None, Some(90), now).unwrap(), at(1, 13, 30));
+
+        for (time, delay) in [
+            (Some("13:30"), Some(5)),
+            (None, None),
+            (Some("25:00"), None),
+        ] {
+            assert!(matches!(
+                resolve_run_at(time, delay, now),
+                Err(AppError::InvalidInput(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::aria2c::Aria2cState;
+use crate::config::settings::DownloadSettings;
+use crate::error::AppError;
+use crate::schedule::queue::{PendingAction, ScheduledAction};
+use crate::schedule::speed::SpeedLimits;
+use chrono::{DateTime, Local, Timelike};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::sync::Notify;
+
+/// 两次检查之间的墙上时间超过这个值，说明系统刚从睡眠中恢复
+const WAKE_DETECTION_THRESHOLD_SECS: i64 = 120;
+
+/// aria2c 无法连接时，一次性操作的重试间隔
+const RETRY_DELAY_SECS: i64 = 30;
+
+/// 后台调度器的句柄，设置变化或 aria2c 重新连接后唤醒调度器立即重新计算
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    notify: Arc<Notify>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+}
+
+/// 计划操作的执行结果，通过 `scheduled-action` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+struct ScheduledActionResult {
+    /// 一次性操作的 ID，每周规则触发时为空
+    id: Option<String>,
+    action: ScheduledAction,
+    error: Option<String>,
+}
+
+/// 启动调度器：每分钟整点计算当前生效的限速方案，变化时调用 changeGlobalOption，
+/// 并执行到期的队列启停计划
+///
+/// 所有时间段都以分钟为边界，在整分钟检查即可准确切换；被唤醒或检测到睡眠恢复时
+/// 无论是否变化都重新发送一次，保证 aria2c 重启后的设置与当前方案一致
+pub fn start_scheduler(
+    app_handle: tauri::AppHandle,
+    aria2c_state: Aria2cState,
+    settings_state: Arc<Mutex<DownloadSettings>>,
+    scheduler: Scheduler,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut applied: Option<SpeedLimits> = None;
+        let mut last_check = Local::now();
+        let mut force = true;
+
+        loop {
+            let now = Local::now();
+            if (now - last_check).num_seconds() > WAKE_DETECTION_THRESHOLD_SECS {
+                println!("System clock jumped, recomputing scheduled speed limits");
+                force = true;
+            }
+            let since = last_check;
+            last_check = now;
+
+            run_queue_schedule(&app_handle, &aria2c_state, &settings_state, since, now).await;
+
+            let limits = match settings_state.lock() {
+                Ok(settings) => Some(settings.speed_limits_at(now.naive_local())),
+                Err(e) => {
+                    eprintln!("Failed to read speed schedule: {}", e);
+                    None
+                }
+            };
+            if let Some(limits) = limits {
+                if force || applied.as_ref() != Some(&limits) {
+                    applied = match apply_speed_limits(&aria2c_state, &limits).await {
+                        Ok(()) => {
+                            println!("Applied speed limits: {:?}", limits);
+                            if let Err(e) = app_handle.emit("speed-limits-changed", &limits) {
+                                eprintln!("Failed to emit speed-limits-changed: {}", e);
+                            }
+                            Some(limits)
+                        }
+                        Err(e) => {
+                            // aria2c 尚未启动或正在重启，连接后会唤醒调度器
+                            eprintln!("Failed to apply scheduled speed limits: {}", e);
+                            None
+                        }
+                    };
+                }
+            }
+            force = false;
+
+            let next_pending = settings_state
+                .lock()
+                .ok()
+                .and_then(|settings| settings.queue_schedule.next_pending_at());
+            tokio::select! {
+                _ = tokio::time::sleep(next_wake(next_pending)) => {}
+                _ = scheduler.notify.notified() => force = true,
+            }
+        }
+    });
+}
+
+async fn apply_speed_limits(
+    aria2c_state: &Aria2cState,
+    limits: &SpeedLimits,
+) -> Result<(), AppError> {
+    let client = aria2c_state.client()?;
+    client
+        .change_global_option(limits.to_aria2c_options())
+        .await
+}
+
+/// 执行到期的队列操作；aria2c 暂时无法连接时，一次性操作放回列表稍后重试
+async fn run_queue_schedule(
+    app_handle: &tauri::AppHandle,
+    aria2c_state: &Aria2cState,
+    settings_state: &Arc<Mutex<DownloadSettings>>,
+    since: DateTime<Local>,
+    now: DateTime<Local>,
+) {
+    let due = match settings_state.lock() {
+        Ok(mut settings) => {
+            let due = settings.queue_schedule.take_due(since, now);
+            if due.iter().any(|(id, _)| id.is_some()) {
+                if let Err(e) = settings.save() {
+                    eprintln!("Failed to save queue schedule: {}", e);
+                }
+            }
+            due
+        }
+        Err(e) => {
+            eprintln!("Failed to read queue schedule: {}", e);
+            return;
+        }
+    };
+
+    for (id, action) in due {
+        let result = execute_action(aria2c_state, &action).await;
+        if let (Err(AppError::Transport(_)), Some(id)) = (&result, &id) {
+            eprintln!("aria2c unavailable, retrying scheduled action {} later", id);
+            if let Ok(mut settings) = settings_state.lock() {
+                settings.queue_schedule.pending.push(PendingAction {
+                    id: id.clone(),
+                    run_at: now.timestamp() + RETRY_DELAY_SECS,
+                    action,
+                });
+                if let Err(e) = settings.save() {
+                    eprintln!("Failed to save queue schedule: {}", e);
+                }
+            }
+            continue;
+        }
+
+        match &result {
+            Ok(()) => println!("Executed scheduled action: {:?}", action),
+            Err(e) => eprintln!("Failed to execute scheduled action {:?}: {}", action, e),
+        }
+        let executed = ScheduledActionResult {
+            id,
+            action,
+            error: result.err().map(|e| e.to_string()),
+        };
+        if let Err(e) = app_handle.emit("scheduled-action", &executed) {
+            eprintln!("Failed to emit scheduled-action: {}", e);
+        }
+    }
+}
+
+async fn execute_action(
+    aria2c_state: &Aria2cState,
+    action: &ScheduledAction,
+) -> Result<(), AppError> {
+    let client = aria2c_state.client()?;
+    match action {
+        ScheduledAction::PauseAll => client.force_pause_all().await,
+        ScheduledAction::UnpauseAll => client.unpause_all().await,
+        ScheduledAction::StartTask { gid } => client.unpause_download(gid).await.map(|_| ()),
+    }
+}
+
+/// 下一次检查前的等待时间：下一个整分钟，或更早到期的一次性操作
+fn next_wake(next_pending: Option<i64>) -> Duration {
+    let until_minute = until_next_minute();
+    match next_pending {
+        Some(run_at) => {
+            let until_pending = (run_at - Local::now().timestamp()).max(0) as u64;
+            until_minute.min(Duration::from_secs(until_pending))
+        }
+        None => until_minute,
+    }
+}
+
+/// 距离下一个整分钟的时间
+fn until_next_minute() -> Duration {
+    let now = Local::now();
+    let elapsed_ms =
+        (now.second() as u64 * 1000 + now.timestamp_subsec_millis() as u64).min(59_999);
+    Duration::from_millis(60_000 - elapsed_ms + 50)
+}
@@ -0,0 +1,65 @@
+use crate::schedule::window::WeeklyWindow;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 按时间段切换的全局限速
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpeedSchedule {
+    /// 关闭时始终使用全局设置中的限速
+    #[serde(default)]
+    pub enabled: bool,
+    /// 限速方案，多个时间段重叠时排在前面的优先
+    #[serde(default)]
+    pub profiles: Vec<SpeedProfile>,
+}
+
+/// 一个时间段内的限速方案
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedProfile {
+    /// 方案名称，例如 "工作时间"
+    pub name: String,
+    #[serde(flatten)]
+    pub window: WeeklyWindow,
+    /// 下载速度限制 (bytes/s, 0表示无限制)
+    pub max_download_speed: u64,
+    /// 上传速度限制 (bytes/s, 0表示无限制)
+    pub max_upload_speed: u64,
+}
+
+/// 当前生效的限速，通过 `speed-limits-changed` 事件发送给前端
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpeedLimits {
+    /// 生效的方案名称，没有方案生效时为空，使用全局设置
+    pub profile: Option<String>,
+    pub max_download_speed: u64,
+    pub max_upload_speed: u64,
+}
+
+impl SpeedSchedule {
+    /// 指定时间生效的方案
+    pub fn active_profile(&self, at: NaiveDateTime) -> Option<&SpeedProfile> {
+        if !self.enabled {
+            return None;
+        }
+        self.profiles
+            .iter()
+            .find(|profile| profile.window.contains(at))
+    }
+}
+
+impl SpeedLimits {
+    /// 对应的 aria2 全局选项，0 也要发送，用于从限速切换回不限速
+    pub fn to_aria2c_options(&self) -> HashMap<String, String> {
+        let mut options = HashMap::new();
+        options.insert(
+            "max-overall-download-limit".to_string(),
+            self.max_download_speed.to_string(),
+        );
+        options.insert(
+            "max-overall-upload-limit".to_string(),
+            self.max_upload_speed.to_string(),
+        );
+        options
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// 一天中的时刻，精确到分钟，序列化为 "HH:MM"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay(u16);
+
+impl TimeOfDay {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid time \"{}\", expected HH:MM", value);
+        let (hour, minute) = value.trim().split_once(':').ok_or_else(invalid)?;
+        let hour: u16 = hour.parse().map_err(|_| invalid())?;
+        let minute: u16 = minute.parse().map_err(|_| invalid())?;
+        if hour > 23 || minute > 59 {
+            return Err(invalid());
+        }
+        Ok(Self(hour * 60 + minute))
+    }
+
+    /// 从零点开始的分钟数
+    pub fn minutes(&self) -> u16 {
+        self.0
+    }
+
+    pub fn to_naive_time(self) -> NaiveTime {
+        NaiveTime::from_hms_opt((self.0 / 60) as u32, (self.0 % 60) as u32, 0).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+impl Serialize for TimeOfDay {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::parse(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 每周重复的时间段
+///
+/// 结束时刻早于开始时刻表示跨越午夜（例如 22:00–06:00），属于开始的那一天；
+/// 两者相同表示全天。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeeklyWindow {
+    /// 生效的星期 ("Mon" … "Sun")，为空表示每天
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+impl WeeklyWindow {
+    /// 指定的本地时间是否处于时间段内
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let day = at.weekday();
+        let minute = (at.hour() * 60 + at.minute()) as u16;
+        let (start, end) = (self.start.minutes(), self.end.minutes());
+
+        if start == end {
+            self.applies_on(day)
+        } else if start < end {
+            self.applies_on(day) && minute >= start && minute < end
+        } else {
+            (self.applies_on(day) && minute >= start)
+                || (self.applies_on(day.pred()) && minute < end)
+        }
+    }
+
+    fn applies_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    /// 2024-01-01 是星期一
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_time(TimeOfDay::parse(time).unwrap().to_naive_time())
+    }
+
+    fn window(days: &[Weekday], start: &str, end: &str) -> WeeklyWindow {
+        WeeklyWindow {
+            days: days.to_vec(),
+            start: TimeOfDay::parse(start).unwrap(),
+            end: TimeOfDay::parse(end).unwrap(),
+        }
+    }
+
+    #[test]
+    fn parses_and_formats_times() {
+        assert_eq!(TimeOfDay::parse(" 7:05 ").unwrap().to_string(), "07:05");
+        assert_eq!(TimeOfDay::parse("23:59").unwrap().minutes(), 23 * 60 + 59);
+        assert!(TimeOfDay::parse("24:00").is_err());
+        assert!(TimeOfDay::parse("12:60").is_err());
+        assert!(TimeOfDay::parse("1200").is_err());
+    }
+
+    #[test]
+    fn same_day_window_excludes_the_end() {
+        let w = window(&[Weekday::Mon], "09:00", "17:00");
+        assert!(!w.contains(at(1, "08:59")));
+        assert!(w.contains(at(1, "09:00")));
+        assert!(w.contains(at(1, "16:59")));
+        assert!(!w.contains(at(1, "17:00")));
+        assert!(!w.contains(at(2, "10:00")));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_the_start_day() {
+        let w = window(&[Weekday::Fri], "22:00", "06:00");
+        // 2024-01-05 星期五，2024-01-06 星期六
+        assert!(w.contains(at(5, "22:00")));
+        assert!(w.contains(at(6, "05:59")));
+        assert!(!w.contains(at(6, "06:00")));
+        assert!(!w.contains(at(6, "22:30")));
+        // 星期五凌晨属于星期四开始的时间段，星期四不在列表中
+        assert!(!w.contains(at(5, "01:00")));
+    }
+
+    #[test]
+    fn overnight_window_wraps_from_sunday_to_monday() {
+        let w = window(&[Weekday::Sun], "23:00", "01:00");
+        // 2024-01-07 星期日，2024-01-08 星期一
+        assert!(w.contains(at(7, "23:30")));
+        assert!(w.contains(at(8, "00:30")));
+        assert!(!w.contains(at(8, "01:00")));
+        assert!(!w.contains(at(9, "00:30")));
+    }
+
+    #[test]
+    fn equal_start_and_end_means_all_day() {
+        let w = window(&[Weekday::Sat, Weekday::Sun], "00:00", "00:00");
+        assert!(w.contains(at(6, "00:00")));
+        assert!(w.contains(at(7, "23:59")));
+        assert!(!w.contains(at(8, "12:00")));
+    }
+
+    #[test]
+    fn empty_days_apply_every_day() {
+        let w = window(&[], "01:00", "02:00");
+        assert!((1..=7).all(|day| w.contains(at(day, "01:30"))));
+    }
+
+    #[test]
+    fn deserializes_from_json() {
+        let w: WeeklyWindow =
+            serde_json::from_str(r#"{"days":["Mon","Tue"],"start":"22:00","end":"06:00"}"#)
+                .unwrap();
+        assert_eq!(w, window(&[Weekday::Mon, Weekday::Tue], "22:00", "06:00"));
+        assert!(
+            serde_json::from_str::<WeeklyWindow>(r#"{"start":"25:00","end":"06:00"}"#).is_err()
+        );
+    }
+}
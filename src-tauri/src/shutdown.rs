@@ -0,0 +1,69 @@
+use crate::aria2c::Aria2cState;
+use crate::config::settings::DownloadSettings;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+
+/// 退出前有序关闭 aria2c
+///
+/// 托盘退出、更新后重启和系统信号最终都会触发 `RunEvent::ExitRequested`，
+/// 在这里统一阻塞等待 aria2c 保存会话并退出。重复调用时进程已不存在，会立即返回。
+pub fn shutdown_aria2c(app: &tauri::AppHandle) {
+    let timeout = app
+        .state::<Arc<Mutex<DownloadSettings>>>()
+        .lock()
+        .map(|settings| settings.shutdown_timeout())
+        .unwrap_or(Duration::from_secs(10));
+    let state = app.state::<Aria2cState>().inner().clone();
+
+    if let Err(e) = tauri::async_runtime::block_on(state.stop_aria2c(timeout)) {
+        eprintln!("Failed to stop aria2c: {}", e);
+    }
+}
+
+/// 监听系统的退出信号 (Ctrl+C、SIGTERM、Windows 关机/注销)，转为正常的应用退出流程
+pub fn listen_for_shutdown_signals(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        wait_for_shutdown_signal().await;
+        println!("Received shutdown signal, exiting...");
+        app_handle.exit(0);
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn wait_for_shutdown_signal() {
+    use tokio::signal::windows::{ctrl_close, ctrl_logoff, ctrl_shutdown};
+
+    match (ctrl_close(), ctrl_logoff(), ctrl_shutdown()) {
+        (Ok(mut close), Ok(mut logoff), Ok(mut shutdown)) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = close.recv() => {}
+                _ = logoff.recv() => {}
+                _ = shutdown.recv() => {}
+            }
+        }
+        _ => {
+            eprintln!("Failed to listen for Windows console shutdown events");
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+/// 最大嵌套层数，防止恶意构造的种子导致栈溢出
+const MAX_DEPTH: usize = 64;
+
+/// bencode 值
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bencode {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Bencode>),
+    Dict(BTreeMap<Vec<u8>, Bencode>),
+}
+
+impl Bencode {
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Bencode::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Bencode::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Bencode]> {
+        match self {
+            Bencode::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Bencode>> {
+        match self {
+            Bencode::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// 读取字典中的字段
+    pub fn get(&self, key: &str) -> Option<&Bencode> {
+        self.as_dict()?.get(key.as_bytes())
+    }
+
+    /// 类型名称，用于错误信息
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Bencode::Int(_) => "integer",
+            Bencode::Bytes(_) => "string",
+            Bencode::List(_) => "list",
+            Bencode::Dict(_) => "dictionary",
+        }
+    }
+}
+
+/// bencode 解析错误，`offset` 为出错位置的字节偏移
+#[derive(Debug, Clone, PartialEq)]
+pub struct BencodeError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for BencodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+/// 解析结果，同时记录顶层字典中 `info` 字段的原始字节范围（用于计算 info-hash）
+#[derive(Debug)]
+pub struct Decoded {
+    pub value: Bencode,
+    pub info_span: Option<Range<usize>>,
+}
+
+/// 解析完整的 bencode 数据，末尾不允许有多余字节
+pub fn decode(data: &[u8]) -> Result<Decoded, BencodeError> {
+    let mut decoder = Decoder {
+        data,
+        pos: 0,
+        info_span: None,
+    };
+    let value = decoder.parse_value(0)?;
+    if decoder.pos != data.len() {
+        return Err(decoder.error("unexpected trailing data after the root value"));
+    }
+
+    Ok(Decoded {
+        value,
+        info_span: decoder.info_span,
+    })
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    info_span: Option<Range<usize>>,
+}
+
+impl Decoder<'_> {
+    fn error(&self, message: &str) -> BencodeError {
+        BencodeError {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Result<u8, BencodeError> {
+        self.data
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| self.error("unexpected end of data"))
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Bencode, BencodeError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+
+        match self.peek()? {
+            b'i' => self.parse_int(),
+            b'l' => self.parse_list(depth),
+            b'd' => self.parse_dict(depth),
+            b'0'..=b'9' => self.parse_bytes().map(Bencode::Bytes),
+            other => Err(self.error(&format!("unexpected byte 0x{:02x}", other))),
+        }
+    }
+
+    /// 读取以 `terminator` 结尾的十进制数字，拒绝前导零和 "-0"
+    fn parse_number(&mut self, terminator: u8, allow_negative: bool) -> Result<i64, BencodeError> {
+        let start = self.pos;
+        let end = self.data[start..]
+            .iter()
+            .position(|&b| b == terminator)
+            .map(|len| start + len)
+            .ok_or_else(|| self.error("unterminated number"))?;
+        let digits = &self.data[start..end];
+
+        let (negative, unsigned) = match digits.first() {
+            Some(b'-') if allow_negative => (true, &digits[1..]),
+            _ => (false, digits),
+        };
+        let leading_zero = unsigned.len() > 1 && unsigned[0] == b'0';
+        let negative_zero = negative && unsigned == b"0";
+        let valid = !unsigned.is_empty()
+            && unsigned.iter().all(u8::is_ascii_digit)
+            && !leading_zero
+            && !negative_zero;
+        if !valid {
+            return Err(self.error("invalid number"));
+        }
+
+        let value = std::str::from_utf8(digits)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .ok_or_else(|| self.error("number out of range"))?;
+        self.pos = end + 1;
+        Ok(value)
+    }
+
+    fn parse_int(&mut self) -> Result<Bencode, BencodeError> {
+        self.pos += 1; // 'i'
+        self.parse_number(b'e', true).map(Bencode::Int)
+    }
+
+    fn parse_bytes(&mut self) -> Result<Vec<u8>, BencodeError> {
+        let length_offset = self.pos;
+        let length = self.parse_number(b':', false)? as usize;
+        let start = self.pos;
+        let end = start
+            .checked_add(length)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| BencodeError {
+                offset: length_offset,
+                message: format!("string length {} exceeds the remaining data", length),
+            })?;
+        self.pos = end;
+        Ok(self.data[start..end].to_vec())
+    }
+
+    fn parse_list(&mut self, depth: usize) -> Result<Bencode, BencodeError> {
+        self.pos += 1; // 'l'
+        let mut list = Vec::new();
+        while self.peek()? != b'e' {
+            list.push(self.parse_value(depth + 1)?);
+        }
+        self.pos += 1;
+        Ok(Bencode::List(list))
+    }
+
+    fn parse_dict(&mut self, depth: usize) -> Result<Bencode, BencodeError> {
+        self.pos += 1; // 'd'
+        let mut dict = BTreeMap::new();
+        while self.peek()? != b'e' {
+            let key_offset = self.pos;
+            if !self.peek()?.is_ascii_digit() {
+                return Err(self.error("dictionary key must be a string"));
+            }
+            let key = self.parse_bytes()?;
+
+            let value_start = self.pos;
+            let value = self.parse_value(depth + 1)?;
+            if depth == 0 && key == b"info" {
+                self.info_span = Some(value_start..self.pos);
+            }
+
+            if dict.insert(key.clone(), value).is_some() {
+                return Err(BencodeError {
+                    offset: key_offset,
+                    message: format!(
+                        "duplicate dictionary key \"{}\"",
+                        String::from_utf8_lossy(&key)
+                    ),
+                });
+            }
+        }
+        self.pos += 1;
+        Ok(Bencode::Dict(dict))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(data: &[u8]) -> BencodeError {
+        decode(data).expect_err("expected a decode error")
+    }
+
+    #[test]
+    fn decodes_nested_values() {
+        let decoded = decode(b"d3:agei-3e4:listl4:spami0eee").unwrap();
+        assert_eq!(decoded.value.get("age"), Some(&Bencode::Int(-3)));
+        let list = decoded
+            .value
+            .get("list")
+            .and_then(Bencode::as_list)
+            .unwrap();
+        assert_eq!(list[0].as_bytes(), Some(&b"spam"[..]));
+        assert_eq!(list[1].as_int(), Some(0));
+    }
+
+    #[test]
+    fn rejects_leading_zeros() {
+        assert_eq!(error(b"i03e").message, "invalid number");
+        assert_eq!(error(b"i-03e").message, "invalid number");
+        assert_eq!(error(b"03:abc").message, "invalid number");
+        assert!(decode(b"0:").is_ok());
+    }
+
+    #[test]
+    fn rejects_negative_zero() {
+        assert_eq!(error(b"i-0e").message, "invalid number");
+        // 字符串长度不能为负
+        assert_eq!(error(b"-1:a").message, "unexpected byte 0x2d");
+    }
+
+    #[test]
+    fn reports_truncated_strings() {
+        let e = error(b"l5:abce");
+        assert_eq!(e.offset, 1);
+        assert_eq!(e.message, "string length 5 exceeds the remaining data");
+        assert_eq!(error(b"i12").message, "unterminated number");
+        assert_eq!(error(b"l1:a").message, "unexpected end of data");
+    }
+
+    #[test]
+    fn rejects_duplicate_keys() {
+        let e = error(b"d1:ai1e1:ai2ee");
+        assert_eq!(e.offset, 7);
+        assert_eq!(e.message, "duplicate dictionary key \"a\"");
+    }
+
+    #[test]
+    fn rejects_non_string_keys_and_trailing_data() {
+        assert_eq!(
+            error(b"di1ei2ee").message,
+            "dictionary key must be a string"
+        );
+        assert_eq!(
+            error(b"i1ei2e").message,
+            "unexpected trailing data after the root value"
+        );
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let nested = |depth: usize| [vec![b'l'; depth], vec![b'e'; depth]].concat();
+        assert!(decode(&nested(MAX_DEPTH + 1)).is_ok());
+        let e = error(&nested(MAX_DEPTH + 2));
+        assert_eq!(e.offset, MAX_DEPTH + 1);
+        assert_eq!(e.message, "nesting too deep");
+    }
+
+    #[test]
+    fn records_the_top_level_info_span() {
+        let data = b"d4:infod1:xi1ee1:yd4:infoi2eee";
+        let decoded = decode(data).unwrap();
+        let span = decoded.info_span.unwrap();
+        assert_eq!(&data[span], b"d1:xi1ee");
+    }
+}
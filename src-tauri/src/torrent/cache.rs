@@ -0,0 +1,41 @@
+use crate::error::AppError;
+use crate::torrent::meta::TorrentMeta;
+use std::path::{Path, PathBuf};
+
+/// 种子文件缓存，以 v1 info-hash 命名 (`<hash>.torrent`)
+///
+/// 添加种子任务时保存一份副本，磁力链接的元数据也下载到这里，重启 BT 任务时从这里读取。
+#[derive(Debug, Clone)]
+pub struct TorrentCache {
+    dir: PathBuf,
+}
+
+impl TorrentCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path_for(&self, info_hash: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.torrent", info_hash.to_ascii_lowercase()))
+    }
+
+    /// 校验并保存种子，返回解析后的元数据；纯 v2 种子没有 v1 info-hash，不保存
+    pub fn store(&self, data: &[u8]) -> Result<TorrentMeta, AppError> {
+        let meta = TorrentMeta::parse(data)?;
+        if let Some(info_hash) = &meta.info_hash_v1 {
+            std::fs::create_dir_all(&self.dir)?;
+            std::fs::write(self.path_for(info_hash), data)?;
+        }
+        Ok(meta)
+    }
+
+    /// 读取缓存的种子内容
+    pub fn load(&self, info_hash: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.path_for(info_hash)).ok()
+    }
+}
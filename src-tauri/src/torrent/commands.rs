@@ -0,0 +1,218 @@
+use crate::aria2c::download_manager::Aria2cClient;
+use crate::aria2c::{Aria2cEvents, Aria2cState, DownloadEventKind};
+use crate::error::AppError;
+use crate::torrent::cache::TorrentCache;
+use crate::torrent::magnet::MagnetLink;
+use crate::torrent::meta::TorrentMeta;
+use base64::Engine;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::MissedTickBehavior;
+
+/// 等待元数据下载的最长时间，没有做种者的磁力链接不会一直占用任务
+const METADATA_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// 查询元数据任务状态的间隔
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 磁力链接的元数据已下载，通过 `magnet-metadata-ready` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+pub struct MagnetMetadata {
+    pub gid: String,
+    /// 保存的种子文件，可直接传给 add_download_torrent 并选择文件
+    pub torrent_path: String,
+    pub meta: TorrentMeta,
+}
+
+/// 元数据下载失败或被取消，通过 `magnet-metadata-failed` 事件发送给前端
+#[derive(Debug, Clone, Serialize)]
+struct MagnetMetadataFailed {
+    gid: String,
+    error: String,
+}
+
+/// 读取种子内容：本地文件路径或 Base64 编码的内容，二者只能指定一个
+pub async fn read_torrent_input(
+    torrent: Option<String>,
+    torrent_base64: Option<String>,
+) -> Result<Vec<u8>, AppError> {
+    match (torrent, torrent_base64) {
+        (Some(path), None) => Ok(tokio::fs::read(&path).await?),
+        (None, Some(data)) => Ok(base64::engine::general_purpose::STANDARD.decode(data.trim())?),
+        _ => Err(AppError::InvalidInput(
+            "Specify exactly one of torrent path or base64 content".to_string(),
+        )),
+    }
+}
+
+/// 解析种子文件，返回名称、info-hash、文件树、Tracker 等元数据
+#[tauri::command]
+pub async fn tell_torrent_info(
+    torrent: Option<String>,
+    torrent_base64: Option<String>,
+) -> Result<TorrentMeta, AppError> {
+    let data = read_torrent_input(torrent, torrent_base64).await?;
+    TorrentMeta::parse(&data)
+}
+
+/// 解析并校验磁力链接
+#[tauri::command]
+pub async fn parse_magnet_link(magnet_link: String) -> Result<MagnetLink, AppError> {
+    MagnetLink::parse(&magnet_link)
+}
+
+/// 只下载磁力链接的元数据并保存为种子文件，不占用下载空间
+///
+/// 立即返回元数据任务的 GID，完成后发送 `magnet-metadata-ready`（包含文件列表），
+/// 失败或被删除时发送 `magnet-metadata-failed`
+#[tauri::command]
+pub async fn fetch_magnet_metadata(
+    magnet_link: String,
+    app: tauri::AppHandle,
+    aria2c_state: tauri::State<'_, Aria2cState>,
+    events: tauri::State<'_, Aria2cEvents>,
+    torrent_cache: tauri::State<'_, TorrentCache>,
+) -> Result<String, AppError> {
+    let magnet = MagnetLink::parse(&magnet_link)?;
+    let info_hash = magnet.info_hash_v1.ok_or_else(|| {
+        AppError::InvalidInput("Metadata pre-fetch requires a btih magnet link".to_string())
+    })?;
+
+    // 种子文件由 aria2 写入本地目录，外部守护进程写入的文件本程序读取不到
+    if aria2c_state.endpoint()?.external {
+        return Err(AppError::InvalidInput(
+            "Metadata pre-fetch is only available with the local aria2c".to_string(),
+        ));
+    }
+
+    // 元数据直接下载到种子缓存目录，之后重启该任务时也能使用
+    let dir = torrent_cache.dir().to_path_buf();
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let mut options = HashMap::new();
+    options.insert("bt-metadata-only".to_string(), "true".to_string());
+    options.insert("bt-save-metadata".to_string(), "true".to_string());
+    options.insert("follow-torrent".to_string(), "false".to_string());
+    options.insert("dir".to_string(), dir.to_string_lossy().to_string());
+
+    // 先订阅再添加，避免错过很快完成的任务
+    let receiver = events.subscribe();
+    let client = aria2c_state.client()?;
+    let gid = client.add_uri(vec![magnet_link], Some(options)).await?;
+
+    // aria2 以 info-hash 命名保存的种子文件，与缓存的命名规则一致
+    let torrent_path = torrent_cache.path_for(&info_hash);
+    tauri::async_runtime::spawn(wait_for_metadata(
+        app,
+        client,
+        receiver,
+        gid.clone(),
+        torrent_path,
+    ));
+
+    Ok(gid)
+}
+
+/// 等待元数据任务结束，读取保存的种子文件并通知前端
+async fn wait_for_metadata(
+    app: tauri::AppHandle,
+    client: Aria2cClient,
+    mut receiver: broadcast::Receiver<crate::aria2c::DownloadEvent>,
+    gid: String,
+    torrent_path: PathBuf,
+) {
+    let deadline = tokio::time::sleep(METADATA_TIMEOUT);
+    tokio::pin!(deadline);
+    // 定期查询任务状态，通知丢失（例如重连期间）时也能结束等待
+    let mut poll = tokio::time::interval(STATUS_POLL_INTERVAL);
+    poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let waited = loop {
+        tokio::select! {
+            received = receiver.recv() => match received {
+                Ok(event) if event.gid == gid => match event.kind {
+                    DownloadEventKind::Complete | DownloadEventKind::BtComplete => break Ok(()),
+                    DownloadEventKind::Error | DownloadEventKind::Stop => break Err(metadata_failed()),
+                    _ => {}
+                },
+                Ok(_) => {}
+                // 错过了部分通知，立即查询任务状态
+                Err(RecvError::Lagged(_)) => poll.reset_immediately(),
+                Err(RecvError::Closed) => return,
+            },
+            _ = poll.tick() => match metadata_finished(&client, &gid).await {
+                Some(true) => break Ok(()),
+                Some(false) => break Err(metadata_failed()),
+                None => {}
+            },
+            _ = &mut deadline => {
+                break Err(AppError::Process(
+                    "Timed out waiting for magnet metadata".to_string(),
+                ));
+            }
+        }
+    };
+
+    let result = match waited {
+        Ok(()) => match tokio::fs::read(&torrent_path).await {
+            Ok(data) => TorrentMeta::parse(&data),
+            Err(e) => Err(AppError::Io(format!(
+                "Failed to read metadata {}: {}",
+                torrent_path.display(),
+                e
+            ))),
+        },
+        Err(e) => Err(e),
+    };
+
+    // 元数据任务已无用，超时时停止任务，否则从 aria2 的已停止列表中移除
+    let _ = client.remove_download(&gid).await;
+
+    match result {
+        Ok(meta) => {
+            let metadata = MagnetMetadata {
+                gid,
+                torrent_path: torrent_path.to_string_lossy().to_string(),
+                meta,
+            };
+            if let Err(e) = app.emit("magnet-metadata-ready", &metadata) {
+                eprintln!("Failed to emit magnet-metadata-ready: {}", e);
+            }
+        }
+        Err(e) => {
+            let failed = MagnetMetadataFailed {
+                gid,
+                error: e.to_string(),
+            };
+            if let Err(e) = app.emit("magnet-metadata-failed", &failed) {
+                eprintln!("Failed to emit magnet-metadata-failed: {}", e);
+            }
+        }
+    }
+}
+
+fn metadata_failed() -> AppError {
+    AppError::Process("Metadata download failed or was cancelled".to_string())
+}
+
+/// 查询元数据任务是否结束：`Some(true)` 完成，`Some(false)` 失败或已删除，`None` 仍在进行
+///
+/// 连接失败（例如 aria2c 正在重启）时不能确定任务状态，按仍在进行处理
+async fn metadata_finished(client: &Aria2cClient, gid: &str) -> Option<bool> {
+    match client
+        .tell_status(gid, Some(vec!["status".to_string()]))
+        .await
+    {
+        Ok(status) => match status.get("status").and_then(|v| v.as_str()) {
+            Some("complete") => Some(true),
+            Some("error") | Some("removed") => Some(false),
+            _ => None,
+        },
+        Err(AppError::Rpc { .. }) => Some(false),
+        Err(_) => None,
+    }
+}
@@ -0,0 +1,200 @@
+use crate::error::AppError;
+use serde::Serialize;
+
+/// 解析后的磁力链接 (BEP 9 / BEP 52)
+#[derive(Debug, Clone, Serialize)]
+pub struct MagnetLink {
+    /// v1 info-hash (xt=urn:btih)，统一为小写十六进制
+    pub info_hash_v1: Option<String>,
+    /// v2 info-hash (xt=urn:btmh)，为去掉 multihash 前缀的 SHA-256 十六进制
+    pub info_hash_v2: Option<String>,
+    /// 显示名称 (dn)
+    pub display_name: Option<String>,
+    /// Tracker (tr)
+    pub trackers: Vec<String>,
+    /// Web 种子 (ws)
+    pub web_seeds: Vec<String>,
+    /// 总大小 (xl)
+    pub exact_length: Option<u64>,
+}
+
+impl MagnetLink {
+    pub fn parse(magnet: &str) -> Result<Self, AppError> {
+        let magnet = magnet.trim();
+        let url =
+            url::Url::parse(magnet).map_err(|e| invalid(&format!("not a valid URI ({})", e)))?;
+        if url.scheme() != "magnet" {
+            return Err(invalid(&format!(
+                "expected the magnet: scheme, found {}:",
+                url.scheme()
+            )));
+        }
+
+        let mut link = MagnetLink {
+            info_hash_v1: None,
+            info_hash_v2: None,
+            display_name: None,
+            trackers: Vec::new(),
+            web_seeds: Vec::new(),
+            exact_length: None,
+        };
+
+        for (key, value) in url.query_pairs() {
+            // 部分客户端使用 xt.1、tr.1 这样带序号的参数名
+            let key = key.split('.').next().unwrap_or_default();
+            match key {
+                "xt" => link.parse_exact_topic(&value)?,
+                "dn" => link.display_name = Some(value.to_string()),
+                "tr" => push_unique(&mut link.trackers, &value),
+                "ws" => push_unique(&mut link.web_seeds, &value),
+                "xl" => {
+                    let length = value
+                        .parse()
+                        .map_err(|_| invalid(&format!("xl: invalid length \"{}\"", value)))?;
+                    link.exact_length = Some(length);
+                }
+                _ => {}
+            }
+        }
+
+        if link.info_hash_v1.is_none() && link.info_hash_v2.is_none() {
+            return Err(invalid("missing xt=urn:btih or xt=urn:btmh"));
+        }
+
+        Ok(link)
+    }
+
+    fn parse_exact_topic(&mut self, topic: &str) -> Result<(), AppError> {
+        let lower = topic.to_ascii_lowercase();
+        if let Some(hash) = lower.strip_prefix("urn:btih:") {
+            self.info_hash_v1 = Some(parse_btih(hash)?);
+        } else if let Some(hash) = lower.strip_prefix("urn:btmh:") {
+            self.info_hash_v2 = Some(parse_btmh(hash)?);
+        }
+        // 其他类型的 xt (ed2k 等) 与 BT 无关，忽略
+        Ok(())
+    }
+}
+
+fn invalid(message: &str) -> AppError {
+    AppError::InvalidInput(format!("Invalid magnet link: {}", message))
+}
+
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    if !value.is_empty() && !list.iter().any(|item| item == value) {
+        list.push(value.to_string());
+    }
+}
+
+/// btih 可以是 40 位十六进制或 32 位 base32
+fn parse_btih(hash: &str) -> Result<String, AppError> {
+    match hash.len() {
+        40 if hash.chars().all(|c| c.is_ascii_hexdigit()) => Ok(hash.to_ascii_lowercase()),
+        32 => {
+            let bytes = decode_base32(hash)
+                .ok_or_else(|| invalid(&format!("btih: invalid base32 hash \"{}\"", hash)))?;
+            Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+        }
+        _ => Err(invalid(&format!(
+            "btih: expected 40 hex or 32 base32 characters, found \"{}\"",
+            hash
+        ))),
+    }
+}
+
+/// btmh 是 multihash 十六进制，目前只支持 SHA-256 (前缀 1220)
+fn parse_btmh(hash: &str) -> Result<String, AppError> {
+    if !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid(&format!("btmh: invalid hex hash \"{}\"", hash)));
+    }
+    match hash.strip_prefix("1220") {
+        Some(digest) if digest.len() == 64 => Ok(digest.to_string()),
+        _ => Err(invalid(&format!(
+            "btmh: expected a SHA-256 multihash (1220 + 64 hex characters), found \"{}\"",
+            hash
+        ))),
+    }
+}
+
+/// RFC 4648 base32 解码 (不带填充)，32 个字符正好解出 20 字节
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in input.chars() {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "ca41b533e1b532b4d8d6f8db8e18b0d3d26ea1b7";
+
+    #[test]
+    fn parses_hex_btih_and_parameters() {
+        let link = MagnetLink::parse(
+            "magnet:?xt=urn:btih:CA41B533E1B532B4D8D6F8DB8E18B0D3D26EA1B7&dn=file%20name\
+             &tr=udp%3A%2F%2Ft.example%3A80&tr.1=udp%3A%2F%2Ft.example%3A80&xl=1024",
+        )
+        .unwrap();
+        assert_eq!(link.info_hash_v1.as_deref(), Some(HASH));
+        assert_eq!(link.display_name.as_deref(), Some("file name"));
+        assert_eq!(link.trackers, vec!["udp://t.example:80"]);
+        assert_eq!(link.exact_length, Some(1024));
+    }
+
+    #[test]
+    fn decodes_base32_btih() {
+        assert_eq!(
+            parse_btih("ZJA3KM7BWUZLJWGW7DNY4GFQ2PJG5INX").unwrap(),
+            HASH
+        );
+        assert_eq!(
+            parse_btih("zja3km7bwuzljwgw7dny4gfq2pjg5inx").unwrap(),
+            HASH
+        );
+        // base32 字母表中没有 0、1、8、9
+        assert!(decode_base32("ZJA3KM7BWUZLJWGW7DNY4GFQ2PJG5IN1").is_none());
+        assert!(parse_btih("abc").is_err());
+    }
+
+    #[test]
+    fn parses_btmh_with_the_sha256_prefix() {
+        let digest = "1803fcd7bdad9e61fab4f904253f5b66f9178744cbd39d44bed83d0eaac17a63";
+        assert_eq!(parse_btmh(&format!("1220{}", digest)).unwrap(), digest);
+        // 1114 是 SHA-1 的 multihash 前缀
+        assert!(parse_btmh(&format!("1114{}", HASH)).is_err());
+        assert!(parse_btmh("1220zz").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_exact_length() {
+        let link = format!("magnet:?xt=urn:btih:{}&xl=-1", HASH);
+        assert_eq!(
+            MagnetLink::parse(&link).unwrap_err().message(),
+            "Invalid magnet link: xl: invalid length \"-1\""
+        );
+    }
+
+    #[test]
+    fn requires_a_bittorrent_topic() {
+        assert!(MagnetLink::parse("magnet:?dn=x&xt=urn:ed2k:abc").is_err());
+        assert!(MagnetLink::parse("http://example.com/?xt=urn:btih:x").is_err());
+    }
+}
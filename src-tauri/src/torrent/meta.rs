@@ -0,0 +1,574 @@
+use crate::error::AppError;
+use crate::torrent::bencode::{self, Bencode};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// 种子文件的元数据
+#[derive(Debug, Clone, Serialize)]
+pub struct TorrentMeta {
+    pub name: String,
+    /// v1 info-hash (SHA-1，十六进制)，纯 v2 种子没有
+    pub info_hash_v1: Option<String>,
+    /// v2 info-hash (SHA-256，十六进制)，仅 v2/混合种子有
+    pub info_hash_v2: Option<String>,
+    pub piece_length: u64,
+    pub total_length: u64,
+    /// 按种子中的顺序排列的文件，`index` 与 aria2 的文件序号一致
+    pub files: Vec<TorrentFile>,
+    /// 目录树，目录的大小为其中所有文件之和
+    pub file_tree: Vec<TorrentFileNode>,
+    /// Tracker 分组 (announce-list)，没有分组时为 announce 单独一组
+    pub trackers: Vec<Vec<String>>,
+    /// Web 种子 (url-list)
+    pub web_seeds: Vec<String>,
+    pub comment: Option<String>,
+    pub created_by: Option<String>,
+    /// 创建时间 (Unix 秒)
+    pub creation_date: Option<i64>,
+    pub private: bool,
+}
+
+/// 种子中的单个文件
+#[derive(Debug, Clone, Serialize)]
+pub struct TorrentFile {
+    /// 文件序号，从 1 开始
+    pub index: usize,
+    /// 相对于种子根目录的路径
+    pub path: Vec<String>,
+    pub length: u64,
+    /// BEP 47 的填充文件，不需要展示给用户
+    pub padding: bool,
+}
+
+/// 目录树节点
+#[derive(Debug, Clone, Serialize)]
+pub struct TorrentFileNode {
+    pub name: String,
+    pub length: u64,
+    /// 文件序号，目录为空
+    pub index: Option<usize>,
+    pub children: Vec<TorrentFileNode>,
+}
+
+impl TorrentMeta {
+    /// 解析种子文件内容
+    pub fn parse(data: &[u8]) -> Result<Self, AppError> {
+        let decoded = bencode::decode(data)
+            .map_err(|e| AppError::InvalidInput(format!("Malformed torrent: {}", e)))?;
+        let root = &decoded.value;
+        if root.as_dict().is_none() {
+            return Err(invalid(&format!(
+                "root: expected a dictionary, found {}",
+                root.type_name()
+            )));
+        }
+
+        let info = root
+            .get("info")
+            .ok_or_else(|| invalid("missing \"info\""))?;
+        if info.as_dict().is_none() {
+            return Err(invalid(&format!(
+                "info: expected a dictionary, found {}",
+                info.type_name()
+            )));
+        }
+        let info_bytes = decoded
+            .info_span
+            .map(|span| &data[span])
+            .ok_or_else(|| invalid("missing \"info\""))?;
+
+        let name = utf8_field(info, "name")
+            .transpose()?
+            .ok_or_else(|| invalid("info.name: missing"))?;
+        if name.is_empty() {
+            return Err(invalid("info.name: empty"));
+        }
+        let piece_length = required_uint(info, "piece length", "info.piece length")?;
+        if piece_length == 0 {
+            return Err(invalid("info.piece length: must be positive"));
+        }
+
+        let meta_version = optional_int(info, "meta version", "info.meta version")?;
+        let is_v2 = meta_version == Some(2);
+        let is_v1 = info.get("pieces").is_some();
+        if !is_v1 && !is_v2 {
+            return Err(invalid(
+                "info: missing \"pieces\" (v1) and \"meta version\" (v2)",
+            ));
+        }
+
+        let files = if is_v1 {
+            let pieces = bytes_field(info, "pieces", "info.pieces")?;
+            if pieces.len() % 20 != 0 {
+                return Err(invalid(&format!(
+                    "info.pieces: length {} is not a multiple of 20",
+                    pieces.len()
+                )));
+            }
+            parse_v1_files(info, &name)?
+        } else {
+            let tree = info
+                .get("file tree")
+                .ok_or_else(|| invalid("info.file tree: missing"))?;
+            let mut files = Vec::new();
+            parse_v2_tree(tree, &mut Vec::new(), "info.file tree", &mut files)?;
+            if files.is_empty() {
+                return Err(invalid("info.file tree: no files"));
+            }
+            // 单文件种子的文件树只有一个以 name 命名的文件，多文件种子以 name 为根目录
+            let single_file = files.len() == 1 && files[0].path.len() == 1;
+            if !single_file {
+                for file in &mut files {
+                    file.path.insert(0, name.clone());
+                }
+            }
+            files
+        };
+
+        let total_length = files.iter().map(|file| file.length).sum();
+        let file_tree = build_file_tree(&files);
+
+        Ok(Self {
+            name,
+            info_hash_v1: is_v1.then(|| to_hex(&Sha1::digest(info_bytes))),
+            info_hash_v2: is_v2.then(|| to_hex(&Sha256::digest(info_bytes))),
+            piece_length,
+            total_length,
+            files,
+            file_tree,
+            trackers: parse_trackers(root)?,
+            web_seeds: parse_web_seeds(root)?,
+            comment: utf8_field(root, "comment").transpose()?,
+            created_by: utf8_field(root, "created by").transpose()?,
+            creation_date: optional_int(root, "creation date", "creation date")?,
+            private: optional_int(info, "private", "info.private")? == Some(1),
+        })
+    }
+}
+
+fn invalid(message: &str) -> AppError {
+    AppError::InvalidInput(format!("Malformed torrent: {}", message))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn bytes_field<'a>(dict: &'a Bencode, key: &str, path: &str) -> Result<&'a [u8], AppError> {
+    let value = dict
+        .get(key)
+        .ok_or_else(|| invalid(&format!("{}: missing", path)))?;
+    value.as_bytes().ok_or_else(|| {
+        invalid(&format!(
+            "{}: expected a string, found {}",
+            path,
+            value.type_name()
+        ))
+    })
+}
+
+/// 读取字符串字段，优先使用 `<key>.utf-8`；字段不存在时返回 None
+fn utf8_field(dict: &Bencode, key: &str) -> Option<Result<String, AppError>> {
+    let utf8_key = format!("{}.utf-8", key);
+    let (key, value) = match dict.get(&utf8_key) {
+        Some(value) => (utf8_key.as_str(), value),
+        None => (key, dict.get(key)?),
+    };
+    Some(
+        value
+            .as_bytes()
+            .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+            .ok_or_else(|| {
+                invalid(&format!(
+                    "{}: expected a string, found {}",
+                    key,
+                    value.type_name()
+                ))
+            }),
+    )
+}
+
+fn optional_int(dict: &Bencode, key: &str, path: &str) -> Result<Option<i64>, AppError> {
+    match dict.get(key) {
+        None => Ok(None),
+        Some(value) => value.as_int().map(Some).ok_or_else(|| {
+            invalid(&format!(
+                "{}: expected an integer, found {}",
+                path,
+                value.type_name()
+            ))
+        }),
+    }
+}
+
+fn required_uint(dict: &Bencode, key: &str, path: &str) -> Result<u64, AppError> {
+    let value =
+        optional_int(dict, key, path)?.ok_or_else(|| invalid(&format!("{}: missing", path)))?;
+    u64::try_from(value).map_err(|_| invalid(&format!("{}: must not be negative", path)))
+}
+
+/// 校验路径的一段，拒绝空名称和可能逃出下载目录的名称
+fn check_path_component(component: &str, path: &str) -> Result<(), AppError> {
+    if component.is_empty()
+        || component == "."
+        || component == ".."
+        || component.contains('/')
+        || component.contains('\\')
+    {
+        return Err(invalid(&format!(
+            "{}: invalid path component \"{}\"",
+            path, component
+        )));
+    }
+    Ok(())
+}
+
+fn is_padding(file: &Bencode) -> bool {
+    file.get("attr")
+        .and_then(|attr| attr.as_bytes())
+        .is_some_and(|attr| attr.contains(&b'p'))
+}
+
+/// v1：单文件种子使用 `length`，多文件种子使用 `files`
+fn parse_v1_files(info: &Bencode, name: &str) -> Result<Vec<TorrentFile>, AppError> {
+    let Some(files) = info.get("files") else {
+        let length = required_uint(info, "length", "info.length")?;
+        return Ok(vec![TorrentFile {
+            index: 1,
+            path: vec![name.to_string()],
+            length,
+            padding: false,
+        }]);
+    };
+
+    let files = files.as_list().ok_or_else(|| {
+        invalid(&format!(
+            "info.files: expected a list, found {}",
+            files.type_name()
+        ))
+    })?;
+    if files.is_empty() {
+        return Err(invalid("info.files: empty"));
+    }
+
+    files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| {
+            let file_path = format!("info.files[{}]", i);
+            if file.as_dict().is_none() {
+                return Err(invalid(&format!(
+                    "{}: expected a dictionary, found {}",
+                    file_path,
+                    file.type_name()
+                )));
+            }
+            let length = required_uint(file, "length", &format!("{}.length", file_path))?;
+
+            let key = if file.get("path.utf-8").is_some() {
+                "path.utf-8"
+            } else {
+                "path"
+            };
+            let components_path = format!("{}.{}", file_path, key);
+            let components = file
+                .get(key)
+                .and_then(|path| path.as_list())
+                .filter(|list| !list.is_empty())
+                .ok_or_else(|| {
+                    invalid(&format!(
+                        "{}: expected a non-empty list of strings",
+                        components_path
+                    ))
+                })?;
+            let path = components
+                .iter()
+                .map(|component| {
+                    let component = component.as_bytes().ok_or_else(|| {
+                        invalid(&format!("{}: expected a list of strings", components_path))
+                    })?;
+                    let component = String::from_utf8_lossy(component).to_string();
+                    check_path_component(&component, &components_path)?;
+                    Ok(component)
+                })
+                .collect::<Result<Vec<_>, AppError>>()?;
+
+            let mut full_path = vec![name.to_string()];
+            full_path.extend(path);
+            Ok(TorrentFile {
+                index: i + 1,
+                path: full_path,
+                length,
+                padding: is_padding(file),
+            })
+        })
+        .collect()
+}
+
+/// v2：`file tree` 是嵌套字典，文件节点为 `{"": {"length": ..., "pieces root": ...}}`
+fn parse_v2_tree(
+    node: &Bencode,
+    prefix: &mut Vec<String>,
+    path: &str,
+    files: &mut Vec<TorrentFile>,
+) -> Result<(), AppError> {
+    let dict = node.as_dict().ok_or_else(|| {
+        invalid(&format!(
+            "{}: expected a dictionary, found {}",
+            path,
+            node.type_name()
+        ))
+    })?;
+
+    for (key, child) in dict {
+        let name = String::from_utf8_lossy(key).to_string();
+        let child_path = format!("{}/{}", path, name);
+
+        if name.is_empty() {
+            if prefix.is_empty() {
+                return Err(invalid(&format!("{}: file without a name", path)));
+            }
+            let length = required_uint(child, "length", &format!("{}.length", path))?;
+            files.push(TorrentFile {
+                index: files.len() + 1,
+                path: prefix.clone(),
+                length,
+                padding: false,
+            });
+            continue;
+        }
+
+        check_path_component(&name, &child_path)?;
+        prefix.push(name);
+        parse_v2_tree(child, prefix, &child_path, files)?;
+        prefix.pop();
+    }
+    Ok(())
+}
+
+fn build_file_tree(files: &[TorrentFile]) -> Vec<TorrentFileNode> {
+    #[derive(Default)]
+    struct Dir {
+        dirs: BTreeMap<String, Dir>,
+        files: Vec<TorrentFileNode>,
+    }
+
+    fn into_nodes(dir: Dir) -> Vec<TorrentFileNode> {
+        let mut nodes: Vec<TorrentFileNode> = dir
+            .dirs
+            .into_iter()
+            .map(|(name, sub_dir)| {
+                let children = into_nodes(sub_dir);
+                TorrentFileNode {
+                    name,
+                    length: children.iter().map(|child| child.length).sum(),
+                    index: None,
+                    children,
+                }
+            })
+            .collect();
+        nodes.extend(dir.files);
+        nodes
+    }
+
+    let mut root = Dir::default();
+    for file in files.iter().filter(|file| !file.padding) {
+        let Some((file_name, dirs)) = file.path.split_last() else {
+            continue;
+        };
+        let mut dir = &mut root;
+        for name in dirs {
+            dir = dir.dirs.entry(name.clone()).or_default();
+        }
+        dir.files.push(TorrentFileNode {
+            name: file_name.clone(),
+            length: file.length,
+            index: Some(file.index),
+            children: Vec::new(),
+        });
+    }
+    into_nodes(root)
+}
+
+fn string_list(value: &Bencode, path: &str) -> Result<Vec<String>, AppError> {
+    let list = value.as_list().ok_or_else(|| {
+        invalid(&format!(
+            "{}: expected a list, found {}",
+            path,
+            value.type_name()
+        ))
+    })?;
+    list.iter()
+        .map(|item| {
+            item.as_bytes()
+                .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+                .ok_or_else(|| invalid(&format!("{}: expected a list of strings", path)))
+        })
+        .collect()
+}
+
+fn parse_trackers(root: &Bencode) -> Result<Vec<Vec<String>>, AppError> {
+    let mut tiers: Vec<Vec<String>> = Vec::new();
+
+    if let Some(announce_list) = root.get("announce-list") {
+        let list = announce_list.as_list().ok_or_else(|| {
+            invalid(&format!(
+                "announce-list: expected a list, found {}",
+                announce_list.type_name()
+            ))
+        })?;
+        for (i, tier) in list.iter().enumerate() {
+            let tier = string_list(tier, &format!("announce-list[{}]", i))?;
+            if !tier.is_empty() {
+                tiers.push(tier);
+            }
+        }
+    }
+
+    if let Some(announce) = utf8_field(root, "announce").transpose()? {
+        let known = tiers.iter().flatten().any(|tracker| tracker == &announce);
+        if !announce.is_empty() && !known {
+            tiers.insert(0, vec![announce]);
+        }
+    }
+
+    Ok(tiers)
+}
+
+fn parse_web_seeds(root: &Bencode) -> Result<Vec<String>, AppError> {
+    match root.get("url-list") {
+        None => Ok(Vec::new()),
+        // 只有一个地址时 url-list 可以是单个字符串
+        Some(Bencode::Bytes(url)) if url.is_empty() => Ok(Vec::new()),
+        Some(Bencode::Bytes(url)) => Ok(vec![String::from_utf8_lossy(url).to_string()]),
+        Some(list) => string_list(list, "url-list"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn torrent(info: &str) -> Vec<u8> {
+        format!("d8:announce20:http://t.example/ann4:info{}e", info).into_bytes()
+    }
+
+    #[test]
+    fn computes_the_v1_info_hash() {
+        let data = torrent(
+            "d6:lengthi1024e4:name8:file.bin12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae",
+        );
+        let meta = TorrentMeta::parse(&data).unwrap();
+        assert_eq!(
+            meta.info_hash_v1.as_deref(),
+            Some("ca41b533e1b532b4d8d6f8db8e18b0d3d26ea1b7")
+        );
+        assert_eq!(meta.info_hash_v2, None);
+        assert_eq!(meta.total_length, 1024);
+        assert_eq!(meta.files.len(), 1);
+        assert_eq!(meta.files[0].path, vec!["file.bin"]);
+        assert_eq!(
+            meta.trackers,
+            vec![vec!["http://t.example/ann".to_string()]]
+        );
+    }
+
+    #[test]
+    fn prefixes_multi_file_paths_and_hides_padding() {
+        let data = torrent(concat!(
+            "d5:filesl",
+            "d6:lengthi10e4:pathl3:sub5:a.mkvee",
+            "d4:attr1:p6:lengthi6e4:pathl4:.pad1:6ee",
+            "d6:lengthi5e4:pathl5:b.srtee",
+            "e4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae",
+        ));
+        let meta = TorrentMeta::parse(&data).unwrap();
+        let paths: Vec<String> = meta.files.iter().map(|f| f.path.join("/")).collect();
+        assert_eq!(paths, vec!["dir/sub/a.mkv", "dir/.pad/6", "dir/b.srt"]);
+        assert!(meta.files[1].padding);
+        assert_eq!(meta.files[2].index, 3);
+
+        // 目录树不包含填充文件
+        assert_eq!(meta.file_tree.len(), 1);
+        let root = &meta.file_tree[0];
+        assert_eq!((root.name.as_str(), root.length), ("dir", 15));
+        let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["sub", "b.srt"]);
+    }
+
+    #[test]
+    fn parses_v2_only_torrents() {
+        let data = torrent(concat!(
+            "d9:file treed5:a.txtd0:d6:lengthi3eee3:subd5:b.txtd0:d6:lengthi5eeeee",
+            "12:meta versioni2e4:name3:dir12:piece lengthi16384ee",
+        ));
+        let meta = TorrentMeta::parse(&data).unwrap();
+        assert_eq!(meta.info_hash_v1, None);
+        assert_eq!(
+            meta.info_hash_v2.as_deref(),
+            Some("dfaeb765bc8e2f9e0108ac779ae600afc142fbeb42fdbb12c8768871f20d56bf")
+        );
+        let paths: Vec<String> = meta.files.iter().map(|f| f.path.join("/")).collect();
+        assert_eq!(paths, vec!["dir/a.txt", "dir/sub/b.txt"]);
+        assert_eq!(meta.total_length, 8);
+    }
+
+    #[test]
+    fn computes_both_hashes_for_hybrid_torrents() {
+        let data = torrent(concat!(
+            "d9:file treed8:file.bind0:d6:lengthi1024e11:pieces root32:",
+            "rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrreee6:lengthi1024e12:meta versioni2e",
+            "4:name8:file.bin12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae",
+        ));
+        let meta = TorrentMeta::parse(&data).unwrap();
+        assert_eq!(
+            meta.info_hash_v1.as_deref(),
+            Some("615a007b0e66637b9c22d86681f9bcb0e1828940")
+        );
+        assert_eq!(
+            meta.info_hash_v2.as_deref(),
+            Some("1803fcd7bdad9e61fab4f904253f5b66f9178744cbd39d44bed83d0eaac17a63")
+        );
+        assert_eq!(meta.files[0].path, vec!["file.bin"]);
+    }
+
+    #[test]
+    fn rejects_parent_directory_components() {
+        let v1 = torrent(concat!(
+            "d5:filesld6:lengthi1e4:pathl2:..6:passwdeee",
+            "4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae",
+        ));
+        assert_eq!(
+            TorrentMeta::parse(&v1).unwrap_err().message(),
+            "Malformed torrent: info.files[0].path: invalid path component \"..\""
+        );
+
+        let v2 = torrent(concat!(
+            "d9:file treed2:..d1:xd0:d6:lengthi1eeeee",
+            "12:meta versioni2e4:name3:dir12:piece lengthi16384ee",
+        ));
+        assert_eq!(
+            TorrentMeta::parse(&v2).unwrap_err().message(),
+            "Malformed torrent: info.file tree/..: invalid path component \"..\""
+        );
+    }
+
+    #[test]
+    fn reports_the_failing_field() {
+        let cases: [(&[u8], &str); 4] = [
+            (b"le", "root: expected a dictionary, found list"),
+            (b"d4:infoi1ee", "info: expected a dictionary, found integer"),
+            (b"d4:infod4:name1:xee", "info.piece length: missing"),
+            (
+                b"d4:infod6:lengthi1e4:name1:x12:piece lengthi1e6:pieces3:abcee",
+                "info.pieces: length 3 is not a multiple of 20",
+            ),
+        ];
+        for (data, expected) in cases {
+            let e = TorrentMeta::parse(data).unwrap_err();
+            assert_eq!(e.message(), format!("Malformed torrent: {}", expected));
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod bencode;
+pub mod cache;
+pub mod commands;
+pub mod magnet;
+pub mod meta;
@@ -31,6 +31,12 @@ export interface DownloadGameResponse {
   error?: string;
 }
 
+// 启动参数和 dlapp:// 链接对应的操作，由后端排队等待前端取走
+export type LaunchAction =
+  | { type: "addUrls"; urls: string[] }
+  | { type: "addMagnet"; uri: string }
+  | { type: "openFile"; path: string };
+
 // 错误响应接口
 export interface ErrorResponse {
   code: number;
@@ -95,6 +101,10 @@ export class Api {
     }
   } 
    
+  static async takeLaunchActions() {
+    return await invoke<LaunchAction[]>('take_launch_actions');
+  }
+
   static async tellStatus(gid:string, keys?:string[]) {
     return await invoke<DownloadTask>('tell_status', { gid, keys });
   }
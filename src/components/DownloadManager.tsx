@@ -19,7 +19,16 @@ import React, { useEffect, useState } from "react";
 import { toast } from "sonner";
 import DownloadTaskItem from "./DownloadTaskItem";
 
-const DownloadManager: React.FC = () => {
+interface DownloadManagerProps {
+  // 等待打开添加对话框的启动参数（链接、磁力链接或本地文件）
+  launchTarget?: string;
+  onLaunchTargetOpened?: () => void;
+}
+
+const DownloadManager: React.FC<DownloadManagerProps> = ({
+  launchTarget,
+  onLaunchTargetOpened,
+}) => {
   const { isConnected, loadDownloads, checkConnection } = useDownloadManager();
 
   const tasks = useTasks();
@@ -63,6 +72,14 @@ const DownloadManager: React.FC = () => {
     };
   }, []);
 
+  // 对话框关闭后再打开下一个启动参数，避免覆盖正在编辑的链接
+  useEffect(() => {
+    if (!launchTarget || showAddTaskDialog) return;
+    setNewTaskUrl(launchTarget);
+    setShowAddTaskDialog(true);
+    onLaunchTargetOpened?.();
+  }, [launchTarget, showAddTaskDialog]);
+
   useEffect(() => {
     loadDownloadStats();
 
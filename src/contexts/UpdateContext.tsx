@@ -84,7 +84,7 @@ export const UpdateProvider: React.FC<{ children: ReactNode }> = ({
   const handleUpdate = async () => {
     setIsDownloading(true);
     try {
-      await updateLoad.current?.downloadAndInstall(async (progress) => {
+      await updateLoad.current?.download((progress) => {
         console.log(JSON.stringify(progress));
         if (progress.event == "Started"){
             progressRef.current.contentLength = progress.data.contentLength || 1
@@ -94,9 +94,11 @@ export const UpdateProvider: React.FC<{ children: ReactNode }> = ({
             setDownloadProgress(Math.floor(progressRef.current.loaded / progressRef.current.contentLength * 100))
         } else {
           console.log(progress.event);
-          await invoke("stop_aria2c");
         }
       });
+      // 安装前等待 aria2c 保存会话并退出
+      await invoke("stop_aria2c");
+      await updateLoad.current?.install();
       await relaunch();
     } catch (err) {
       console.log(err);
@@ -1,11 +1,11 @@
+import { Api } from "@/api/api";
 import DownloadManager from "@/components/DownloadManager";
 import { listen } from "@tauri-apps/api/event";
-import React, { useEffect } from "react";
-import { useSearchParams } from "react-router-dom";
+import React, { useEffect, useState } from "react";
 
 const Downloads: React.FC = () => {
-  const [searchParams] = useSearchParams();
- 
+  const [launchTargets, setLaunchTargets] = useState<string[]>([]);
+
   useEffect(() => {
     let unli: any = null;
 
@@ -27,20 +27,59 @@ const Downloads: React.FC = () => {
     };
   }, []);
 
-
+  // 取走启动参数和第二个实例转交的操作，每个链接或文件依次打开添加对话框
   useEffect(() => {
-    console.log(searchParams.get("params"));
-    let pp = searchParams.get("params");
-    if (pp) {
-    //  todo :添加您的命令
-    }
+    let unli: any = null;
+
+    const drain = async () => {
+      try {
+        const actions = await Api.takeLaunchActions();
+        const targets = actions.flatMap((action) => {
+          switch (action.type) {
+            case "addUrls":
+              return action.urls;
+            case "addMagnet":
+              return [action.uri];
+            case "openFile":
+              return [action.path];
+          }
+        });
+        if (targets.length > 0) {
+          setLaunchTargets((pending) => [...pending, ...targets]);
+        }
+      } catch (error) {
+        console.error("获取启动操作失败:", error);
+      }
+    };
+
+    listen("launch-actions", () => {
+      drain();
+    }).then((unlisten) => {
+      unli = unlisten;
+    });
+    drain();
+
+    return () => {
+      if (unli) {
+        unli();
+      } else {
+        setTimeout(() => {
+          if (unli) unli();
+        }, 1000);
+      }
+    };
   }, []);
 
   return (
     // 修改背景色为 Download Pro 风格
     <div className="h-[calc(100vh-48px)] flex flex-col bg-[#F8F9FA]">
       {/* 下载管理器 */}
-      <DownloadManager />
+      <DownloadManager
+        launchTarget={launchTargets[0]}
+        onLaunchTargetOpened={() =>
+          setLaunchTargets((pending) => pending.slice(1))
+        }
+      />
     </div>
   );
 };